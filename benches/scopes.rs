@@ -0,0 +1,59 @@
+//! Benchmarks of variable scopes. To compare them with the flat map of all variables used before the scope chain, run
+//! them with `--save-baseline` on the commit before it and with `--baseline` on the current tree.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use libhier::hier::Hier;
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+/// Creates an instance with many variables, which makes costs proportional to all variables (instead of the current scope) visible.
+fn hier_with_globals(code: &str) -> Hier {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    for i in 0..1000 {
+        hier.add_variable(format!("global{}", i), Value::NUMBER(i as f64));
+    }
+
+    hier.add_variable("numbers".to_string(), Value::LIST((0..1000).map(|i| Value::NUMBER(i as f64)).collect()));
+    hier.try_run(code.to_string()).unwrap();
+
+    hier
+}
+
+fn while_loop(criterion: &mut Criterion) {
+    let mut hier = hier_with_globals("(@count (| n) { (@i 0) (while { (< i n) } { (@x i) (=i (+ i 1)) }) i })");
+
+    criterion.bench_function("while loop with locals", |bencher| bencher.iter(|| {
+        hier.call("count", vec![Value::NUMBER(1000f64)]).unwrap()
+    }));
+}
+
+fn for_loop(criterion: &mut Criterion) {
+    let mut hier = hier_with_globals("(@sum (| list) { (@total 0) (for list { (=total (+ total element)) }) total })");
+
+    criterion.bench_function("for loop over list", |bencher| bencher.iter(|| {
+        hier.call("sum", vec![hier.get_variable("numbers").unwrap()]).unwrap()
+    }));
+}
+
+fn nested_scopes(criterion: &mut Criterion) {
+    let mut hier = hier_with_globals("(@nested (| a) { (repeat 200 { (@b 2) (repeat 5 { (@c (+ a b global999)) }) }) })");
+
+    criterion.bench_function("lookups through nested scopes", |bencher| bencher.iter(|| {
+        hier.call("nested", vec![Value::NUMBER(1f64)]).unwrap()
+    }));
+}
+
+fn recursion(criterion: &mut Criterion) {
+    let mut hier = hier_with_globals("(@fibonacci (| n) { (if (< n 2) { n } { (+ (fibonacci (- n 1)) (fibonacci (- n 2))) }) })");
+
+    criterion.bench_function("recursive calls", |bencher| bencher.iter(|| {
+        hier.call("fibonacci", vec![Value::NUMBER(12f64)]).unwrap()
+    }));
+}
+
+criterion_group!(benches, while_loop, for_loop, nested_scopes, recursion);
+criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,53 @@
+use std::rc::Rc;
+use crate::expression::Block;
+use crate::interpolated_string::InterpolatedString;
+use crate::location::Location;
+use crate::parameter::Parameter;
+use crate::value::Value;
+
+/// Instruction of the stack VM.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    /// Pushes a value.
+    CONSTANT(Value),
+    /// Pushes a string, with interpolated expressions resolved.
+    STRING(Rc<InterpolatedString>),
+    /// Pushes value of a variable, with depth of the scope declaring it, if it is known.
+    GET(String, Option<usize>),
+    /// Pops given number of arguments and pushes result of calling the function, with arguments at given indices passed by name.
+    CALL(String, usize, Vec<usize>),
+    /// Pops given number of arguments (first is the object) and pushes result of calling the method, with arguments at given
+    /// indices passed by name.
+    METHOD(String, usize, Vec<usize>),
+    /// Pops given number of values and pushes them as a list.
+    LIST(usize),
+    /// Pops default values of parameters which have them and pushes function arguments with them.
+    PARAMETERS(Vec<Parameter>),
+    /// Pops a value and pushes it as a key value with given key.
+    KEY_VALUE(String),
+    /// If the value on top is function arguments, replaces it with an anonymous function, which is created from a list of given
+    /// length and a block (if the second element of the list is a block), and jumps to given instruction.
+    FUNCTION(usize, Option<Rc<Block>>, usize),
+    /// Returns a runtime error.
+    ERROR(String),
+    /// Pops result of an expression of a block.
+    STATEMENT,
+    /// Pops the result and returns it.
+    RETURN
+}
+
+/// Compiled code. Instructions setting the interpreted location have it stored at the same index.
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    pub instructions: Vec<Instruction>,
+    pub locations: Vec<Option<Location>>
+}
+
+impl Chunk {
+    pub fn push(&mut self, instruction: Instruction, location: Option<Location>) -> usize {
+        self.instructions.push(instruction);
+        self.locations.push(location);
+        self.instructions.len() - 1
+    }
+}
\ No newline at end of file
@@ -0,0 +1,157 @@
+use std::rc::Rc;
+use crate::bytecode::{Chunk, Instruction};
+use crate::environment::Environment;
+use crate::expression::Expression;
+use crate::location::Location;
+use crate::parameter::Parameter;
+use crate::parser::GET_FUNCTION;
+use crate::types::Type;
+use crate::value::Value;
+
+/// Compiles expressions to bytecode for the VM. Instructions set the interpreted location at the same points as the tree-walking
+/// interpreter does, so errors are reported at the same locations.
+#[derive(Default)]
+pub struct Compiler {
+    chunk: Chunk
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles a block, which returns result of its last expression.
+    pub fn compile_block(mut self, expressions: &[Expression]) -> Chunk {
+        if expressions.is_empty() {
+            self.chunk.push(Instruction::CONSTANT(Value::NULL), None);
+        }
+
+        for (i, expression) in expressions.iter().enumerate() {
+            self.expression(expression);
+
+            if i + 1 < expressions.len() {
+                self.chunk.push(Instruction::STATEMENT, None);
+            }
+        }
+
+        self.chunk.push(Instruction::RETURN, None);
+        self.chunk
+    }
+
+    pub fn compile_expression(mut self, expression: &Expression) -> Chunk {
+        self.expression(expression);
+        self.chunk.push(Instruction::RETURN, None);
+        self.chunk
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::STRING(string, location) => {
+                self.chunk.push(Instruction::STRING(Rc::new(string.clone())), Some(location.clone()));
+            },
+            Expression::NUMBER(number, location) => {
+                self.chunk.push(Instruction::CONSTANT(Value::NUMBER(*number)), Some(location.clone()));
+            },
+            Expression::VALUE(value) => {
+                self.chunk.push(Instruction::CONSTANT(value.clone()), Some(Location::empty()));
+            },
+            Expression::IDENTIFIER(name, location, depth) => {
+                let instruction = match name as &str {
+                    "true" => Instruction::CONSTANT(Value::BOOL(true)),
+                    "false" => Instruction::CONSTANT(Value::BOOL(false)),
+                    "null" => Instruction::CONSTANT(Value::NULL),
+                    _ => match Type::get_for_name(name) {
+                        Some(a_type) => Instruction::CONSTANT(Value::TYPE(a_type)),
+                        None => Instruction::GET(name.clone(), *depth)
+                    }
+                };
+
+                self.chunk.push(instruction, Some(location.clone()));
+            },
+            Expression::BLOCK(block, location) => {
+                self.chunk.push(Instruction::CONSTANT(Value::BLOCK(block.clone())), Some(location.clone()));
+            },
+            Expression::KEY_VALUE(key, expression, _) => {
+                self.expression(expression);
+                self.chunk.push(Instruction::KEY_VALUE(key.clone()), None);
+            },
+            Expression::PROPERTY(expression, identifier, _) => {
+                self.expression(expression);
+                self.chunk.push(Instruction::CONSTANT(Value::STRING(identifier.clone())), None);
+                self.chunk.push(Instruction::CALL(GET_FUNCTION.to_string(), 2, vec![]), None);
+            },
+            Expression::LIST(list, location) => self.list(list, location)
+        }
+    }
+
+    fn list(&mut self, list: &[Expression], location: &Location) {
+        match list.first() {
+            None => {
+                self.chunk.push(Instruction::CONSTANT(Value::NULL), Some(location.clone()));
+            },
+            Some(Expression::IDENTIFIER(name, _, _)) if name == "|" => {
+                let parameters = match Parameter::parse(&list[1..]) {
+                    Ok(parameters) => parameters,
+                    Err(error) => {
+                        self.chunk.push(Instruction::ERROR(error), Some(location.clone()));
+                        return;
+                    }
+                };
+
+                if parameters.iter().all(|(_, default)| default.is_none()) {
+                    let parameters = parameters.into_iter().map(|(parameter, _)| parameter).collect();
+                    self.chunk.push(Instruction::CONSTANT(Value::FUNCTION_ARGUMENTS(parameters)), Some(location.clone()));
+                    return;
+                }
+
+                for default in parameters.iter().filter_map(|(_, default)| *default) {
+                    self.expression(default);
+                }
+
+                let parameters = parameters.into_iter().map(|(parameter, _)| parameter).collect();
+                self.chunk.push(Instruction::PARAMETERS(parameters), None);
+            },
+            Some(Expression::IDENTIFIER(name, _, _)) => {
+                for argument in &list[1..] {
+                    self.expression(argument);
+                }
+
+                // Without arguments, nothing else sets the location of the call.
+                let location = if list.len() == 1 { Some(location.clone()) } else { None };
+                let named = Environment::named_arguments(&list[1..]);
+                self.chunk.push(Instruction::CALL(name.clone(), list.len() - 1, named), location);
+            },
+            Some(Expression::PROPERTY(expression, identifier, _)) => {
+                self.expression(expression);
+
+                for argument in &list[1..] {
+                    self.expression(argument);
+                }
+
+                // Object is the first argument.
+                let named = Environment::named_arguments(&list[1..]).into_iter().map(|i| i + 1).collect();
+                self.chunk.push(Instruction::METHOD(identifier.clone(), list.len(), named), None);
+            },
+            Some(head) => {
+                self.expression(head);
+
+                let block = match list.get(1) {
+                    Some(Expression::BLOCK(block, _)) => Some(block.clone()),
+                    _ => None
+                };
+
+                let function = self.chunk.push(Instruction::FUNCTION(list.len(), block, 0), None);
+
+                for expression in &list[1..] {
+                    self.expression(expression);
+                }
+
+                let end = self.chunk.push(Instruction::LIST(list.len()), None) + 1;
+
+                if let Instruction::FUNCTION(_, _, target) = &mut self.chunk.instructions[function] {
+                    *target = end;
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use crate::environment::Environment;
+use crate::error::HierError;
+use crate::value::{NativeFunction, Value};
+
+/// Error of converting a value to a Rust type.
+#[derive(Debug, Clone)]
+pub struct ConversionError {
+    pub message: String
+}
+
+impl ConversionError {
+    pub fn expected(expected: &str, found: &Value) -> Self {
+        Self {
+            message: format!("expected {}, but {} of type {} was found", expected, found.text_representation(), found.get_type().text_representation())
+        }
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Conversion of a Rust type to a value.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// Conversion of a value to a Rust type.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, ConversionError>;
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::NULL
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::NUMBER(self)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::NUMBER(number) = value {
+            Ok(*number)
+        } else {
+            Err(ConversionError::expected("Number", value))
+        }
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> Value {
+        Value::NUMBER(self as f64)
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::NUMBER(number) = value {
+            if number.fract() == 0f64 && *number >= i64::MIN as f64 && *number <= i64::MAX as f64 {
+                return Ok(*number as i64);
+            }
+        }
+
+        Err(ConversionError::expected("integer Number", value))
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::STRING(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::STRING(self.to_string())
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::STRING(string) = value {
+            Ok(string.clone())
+        } else {
+            Err(ConversionError::expected("String", value))
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::BOOL(self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::BOOL(boolean) = value {
+            Ok(*boolean)
+        } else {
+            Err(ConversionError::expected("Bool", value))
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::LIST(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::LIST(list) = value {
+            list.iter()
+                .enumerate()
+                .map(|(i, element)| T::from_value(element).map_err(|error| ConversionError { message: format!("{} (element {} of list)", error.message, i) }))
+                .collect()
+        } else {
+            Err(ConversionError::expected("List", value))
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self) -> Value {
+        Value::TABLE(self.into_iter().map(|(key, value)| (Value::STRING(key), value.into_value())).collect())
+    }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::TABLE(table) = value {
+            table.iter()
+                .map(|(key, element)| {
+                    let Value::STRING(key) = key else {
+                        return Err(ConversionError::expected("String (table key)", key));
+                    };
+
+                    T::from_value(element)
+                        .map(|element| (key.clone(), element))
+                        .map_err(|error| ConversionError { message: format!("{} (key {} of table)", error.message, key) })
+                })
+                .collect()
+        } else {
+            Err(ConversionError::expected("Table", value))
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::NULL
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, ConversionError> {
+        if let Value::NULL = value {
+            Ok(None)
+        } else {
+            T::from_value(value).map(Some)
+        }
+    }
+}
+
+/// Result of a typed native function: either a value or a result with it.
+pub trait IntoResult {
+    fn into_result(self) -> Result<Value, HierError>;
+}
+
+impl<T: IntoValue> IntoResult for T {
+    fn into_result(self) -> Result<Value, HierError> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue> IntoResult for Result<T, HierError> {
+    fn into_result(self) -> Result<Value, HierError> {
+        self.map(IntoValue::into_value)
+    }
+}
+
+/// Rust function with arguments and result convertible from and to values. Arguments are a tuple of argument types.
+pub trait TypedFunction<Arguments> {
+    fn arity() -> i64;
+    fn into_native_function(self, name: String) -> NativeFunction;
+}
+
+fn argument<T: FromValue>(environment: &Environment, name: &str, arguments: &[Value], index: usize) -> Result<T, HierError> {
+    T::from_value(&arguments[index])
+        .map_err(|error| environment.error(&format!("Invalid argument {} of function {}: {}.", index + 1, name, error)))
+}
+
+macro_rules! typed_function {
+    ($arity:expr $(, $argument:ident $index:tt)*) => {
+        impl<F, R $(, $argument)*> TypedFunction<($($argument,)*)> for F where F: Fn($($argument),*) -> R + 'static, R: IntoResult $(, $argument: FromValue)* {
+            fn arity() -> i64 {
+                $arity
+            }
+
+            #[allow(unused_variables)]
+            fn into_native_function(self, name: String) -> NativeFunction {
+                Rc::new(move |environment: &mut Environment, arguments: Vec<Value>| {
+                    self($(argument::<$argument>(environment, &name, &arguments, $index)?),*).into_result()
+                })
+            }
+        }
+    };
+}
+
+typed_function!(0);
+typed_function!(1, A 0);
+typed_function!(2, A 0, B 1);
+typed_function!(3, A 0, B 1, C 2);
+typed_function!(4, A 0, B 1, C 2, D 3);
+typed_function!(5, A 0, B 1, C 2, D 3, E 4);
+typed_function!(6, A 0, B 1, C 2, D 3, E 4, G 5);
\ No newline at end of file
@@ -0,0 +1,52 @@
+use crate::error::{ErrorKind, HierError};
+use crate::location::Span;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Severity {
+    ERROR,
+    WARNING
+}
+
+/// A problem found in the code, collected instead of being printed, so all of them can be shown at once.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: String, span: Span) -> Self {
+        Self {
+            severity,
+            message,
+            span
+        }
+    }
+
+    pub fn error(message: String, span: Span) -> Self {
+        Self::new(Severity::ERROR, message, span)
+    }
+
+    pub fn warning(message: String, span: Span) -> Self {
+        Self::new(Severity::WARNING, message, span)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::ERROR
+    }
+
+    /// Converts the first error from diagnostics into an error of given kind.
+    pub fn first_error(diagnostics: &[Diagnostic], kind: ErrorKind) -> Option<HierError> {
+        diagnostics.iter()
+            .find(|diagnostic| diagnostic.is_error())
+            .map(|diagnostic| HierError::new(kind, diagnostic.message.clone(), diagnostic.span.start.clone()))
+    }
+}
+
+impl From<HierError> for Diagnostic {
+    fn from(error: HierError) -> Self {
+        Diagnostic::error(error.message, Span::at(error.location))
+    }
+}
\ No newline at end of file
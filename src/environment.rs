@@ -1,237 +1,521 @@
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+use crate::diagnostic::Diagnostic;
+use crate::error::{ErrorKind, HierError};
 use crate::expression::Expression;
-use crate::interpreter::warning;
+use crate::input::{Source, stdin_source};
 use crate::location::Location;
-use crate::report;
+use crate::output::{Sink, stderr_sink, stdout_sink};
+use crate::parameter::Parameter;
+use crate::parser::{GET_FUNCTION, SET_FUNCTION};
+use crate::signal::Signal;
 use crate::value::Value;
+use crate::vm::Backend;
 
+/// Scope of variables, shared by the environment and functions defined in it.
+pub type Scope = Rc<RefCell<Frame>>;
 
-type Scope = u64;
+/// Scope in which a function was defined. A function stored in that scope refers to it weakly, because otherwise they would keep
+/// each other alive. It refers to it strongly again, when it is read from the scope.
+#[derive(Clone)]
+pub enum Closure {
+    STRONG(Scope),
+    WEAK(Weak<RefCell<Frame>>)
+}
+
+impl Closure {
+    /// Returns the scope, or none if it no longer exists.
+    pub fn scope(&self) -> Option<Scope> {
+        match self {
+            Closure::STRONG(scope) => Some(scope.clone()),
+            Closure::WEAK(scope) => scope.upgrade()
+        }
+    }
+
+    fn as_ptr(&self) -> *const RefCell<Frame> {
+        match self {
+            Closure::STRONG(scope) => Rc::as_ptr(scope),
+            Closure::WEAK(scope) => Weak::as_ptr(scope)
+        }
+    }
+}
+
+/// Closures are equal if they refer to the same scope.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.as_ptr(), other.as_ptr())
+    }
+}
+
+/// Variables declared in a scope, linked to the scope that encloses it.
+#[derive(Default)]
+pub struct Frame {
+    pub values: HashMap<String, Value>,
+    pub enclosing: Option<Scope>
+}
+
+impl Frame {
+    pub fn new_scope(enclosing: Option<Scope>) -> Scope {
+        Rc::new(RefCell::new(Frame { values: HashMap::new(), enclosing }))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        if let Some(value) = self.values.get(key) {
+            Some(Self::read(value))
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get(key)
+        } else {
+            None
+        }
+    }
+
+    /// Returns value of the variable in the nearest scope declaring it, if it is a function.
+    pub fn get_function(&self, key: &str) -> Option<Value> {
+        match self.values.get(key) {
+            Some(value @ (Value::FUNCTION(_, _, _) | Value::NATIVE_FUNCTION(_, _))) => Some(Self::read(value)),
+            Some(_) => None,
+            None => self.enclosing.as_ref().and_then(|enclosing| enclosing.borrow().get_function(key))
+        }
+    }
+
+    /// Returns value of the variable declared in this scope.
+    pub fn get_own(&self, key: &str) -> Option<Value> {
+        self.values.get(key).map(Self::read)
+    }
+
+    /// Declares the variable in this scope, returning its previous value.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        let value = self.own(value);
+        self.values.insert(key, value)
+    }
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
-pub struct VariableId(pub Scope, pub String);
+    /// Assigns a value to the variable in the nearest scope declaring it. Returns false if it isn't declared.
+    pub fn assign(&mut self, key: &str, value: Value) -> bool {
+        if self.values.contains_key(key) {
+            let value = self.own(value);
+
+            if let Some(variable) = self.values.get_mut(key) {
+                *variable = value;
+            }
+
+            true
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(key, value)
+        } else {
+            false
+        }
+    }
+
+    /// Prepares a value to be stored in this scope. Functions defined in it refer to it weakly, so they don't keep each other alive.
+    fn own(&self, value: Value) -> Value {
+        match value {
+            Value::FUNCTION(parameters, body, Closure::STRONG(scope)) if std::ptr::eq(RefCell::as_ptr(&scope), self) => {
+                Value::FUNCTION(parameters, body, Closure::WEAK(Rc::downgrade(&scope)))
+            },
+            value => value
+        }
+    }
+
+    /// Returns a value stored in a scope, with functions defined in it referring to it strongly again, so they can outlive it.
+    fn read(value: &Value) -> Value {
+        match value {
+            Value::FUNCTION(parameters, body, Closure::WEAK(scope)) => match scope.upgrade() {
+                Some(scope) => Value::FUNCTION(parameters.clone(), body.clone(), Closure::STRONG(scope)),
+                None => value.clone()
+            },
+            value => value.clone()
+        }
+    }
+}
+
+/// Reads contents of a module for given path.
+pub type ModuleReader = Rc<dyn Fn(String) -> String>;
+
+/// Called when the code panics. If it returns, the panic is returned as a runtime error.
+pub type ExitHandler = Rc<dyn Fn()>;
+
+/// Returns value of a property (given by name) of a foreign object.
+pub type ForeignPropertyGetter = Rc<dyn Fn(&mut Environment, &Value, &str) -> Result<Value, HierError>>;
 
 #[derive(Clone)]
 pub struct Environment {
+    /// Innermost scope, in which variables are declared.
     pub scope: Scope,
+    /// Outermost scope of the module, in which host functions and variables are declared.
+    pub globals: Scope,
     pub path: String,
-    pub values: HashMap<VariableId, Value>,
     pub code: Expression,
     is_in_repl: bool,
-    pub module_reader: fn(String) -> String,
-    pub exit_handler: fn() -> !,
-    pub current_interpreting_location: Location
+    pub module_reader: ModuleReader,
+    pub exit_handler: ExitHandler,
+    pub current_interpreting_location: Location,
+    pub diagnostics: Vec<Diagnostic>,
+    pub output: Sink,
+    pub error_output: Sink,
+    pub input: Source,
+    /// Backend running the code.
+    pub backend: Backend,
+    /// Control flow signal being propagated, if any. Code running blocks must stop when it is set.
+    pub signal: Option<Signal>,
+    user_data: HashMap<TypeId, Rc<dyn Any>>,
+    foreign_properties: HashMap<String, ForeignPropertyGetter>,
+    /// Names of variables that were given a function, which shadows the built-in function with the same name.
+    function_names: HashSet<String>,
+    /// Indices of arguments passed by name to the native function being called.
+    named_argument_indices: Vec<usize>,
+    /// Scopes captured by functions (shared with imported modules). They may be kept alive by reference cycles, for example by a
+    /// function stored in a scope enclosed by the one it captures, so they are cleared by clear_scopes.
+    captured_scopes: Rc<RefCell<Vec<Weak<RefCell<Frame>>>>>
 }
 
 impl Environment {
-    pub fn new(is_in_repl: bool, path: String, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> Self {
+    pub fn new(is_in_repl: bool, path: String, module_reader: ModuleReader, exit_handler: ExitHandler) -> Self {
+        let globals = Frame::new_scope(None);
+
         Self {
-            scope: 0,
-            values: HashMap::new(),
+            scope: globals.clone(),
+            globals,
             code: Expression::LIST(vec![], Location::empty()),
             path,
             is_in_repl,
             module_reader,
             exit_handler,
-            current_interpreting_location: Location::empty()
+            current_interpreting_location: Location::empty(),
+            diagnostics: vec![],
+            output: stdout_sink(),
+            error_output: stderr_sink(),
+            input: stdin_source(),
+            backend: Backend::default(),
+            signal: None,
+            user_data: HashMap::new(),
+            foreign_properties: HashMap::new(),
+            function_names: HashSet::new(),
+            named_argument_indices: vec![],
+            captured_scopes: Rc::new(RefCell::new(vec![]))
         }
     }
 
-    pub fn error(&self, error: &str) -> ! {
-        report(error, self.current_interpreting_location.clone());
+    pub fn is_in_repl(&self) -> bool {
+        self.is_in_repl
+    }
 
-        if self.is_in_repl {
-            panic!("");
-        } else {
-            (self.exit_handler)()
-        }
+    /// Creates a runtime error at the location that is currently being interpreted.
+    pub fn error(&self, error: &str) -> HierError {
+        HierError::new(ErrorKind::RUNTIME, error.to_string(), self.current_interpreting_location.clone())
     }
 
-    pub fn new_with_code(code: Expression, is_in_repl: bool, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> Self {
+    pub fn new_with_code(code: Expression, is_in_repl: bool, module_reader: ModuleReader, exit_handler: ExitHandler) -> Self {
+        let globals = Frame::new_scope(None);
+
         Self {
-            scope: 0,
-            values: HashMap::new(),
+            scope: globals.clone(),
+            globals,
             code,
             path: String::new(),
             is_in_repl,
             module_reader,
             exit_handler,
-            current_interpreting_location: Location::empty()
+            current_interpreting_location: Location::empty(),
+            diagnostics: vec![],
+            output: stdout_sink(),
+            error_output: stderr_sink(),
+            input: stdin_source(),
+            backend: Backend::default(),
+            signal: None,
+            user_data: HashMap::new(),
+            foreign_properties: HashMap::new(),
+            function_names: HashSet::new(),
+            named_argument_indices: vec![],
+            captured_scopes: Rc::new(RefCell::new(vec![]))
         }
     }
-    pub fn begin_scope(&mut self) {
-        self.scope += 1;
+
+    /// Creates an environment for another module (imported or evaluated), sharing host callbacks, sinks and input with this one.
+    pub fn new_module(&self, path: String) -> Self {
+        let mut environment = Environment::new(false, path, self.module_reader.clone(), self.exit_handler.clone());
+
+        environment.output = self.output.clone();
+        environment.error_output = self.error_output.clone();
+        environment.input = self.input.clone();
+        environment.backend = self.backend;
+        environment.user_data = self.user_data.clone();
+        environment.foreign_properties = self.foreign_properties.clone();
+        environment.captured_scopes = self.captured_scopes.clone();
+
+        environment
     }
 
-    pub fn end_scope(&mut self) {
-        if self.scope == 0 {
-            self.error("Ended scope that didn't exist.");
-        } else {
-            for (key, _) in self.values.clone() {
-                if key.0 == self.scope {
-                    self.values.remove(&key);
-                }
+    /// Returns closure of a function defined in the current scope.
+    pub(crate) fn closure(&mut self) -> Closure {
+        let mut captured_scopes = self.captured_scopes.borrow_mut();
+
+        if !captured_scopes.last().is_some_and(|last| std::ptr::eq(last.as_ptr(), Rc::as_ptr(&self.scope))) {
+            // Scopes that no longer exist are removed before the list grows, so it is at most twice as long as needed.
+            if captured_scopes.len() == captured_scopes.capacity() {
+                captured_scopes.retain(|scope| scope.strong_count() > 0);
             }
 
-            self.scope -= 1;
+            captured_scopes.push(Rc::downgrade(&self.scope));
         }
+
+        Closure::STRONG(self.scope.clone())
     }
 
-    pub fn get(&self, key: String) -> Value {
-        if key.contains('#') {
-            // We can unwrap, because it is nil only if the delimiter is not present, but we can be sure, because we checked.
-            let path = key.split_once("#").unwrap();
+    /// Removes variables of the module and of all scopes captured by functions, breaking reference cycles between functions and
+    /// scopes. Functions defined in the module can't be used after it.
+    pub(crate) fn clear_scopes(&mut self) {
+        let captured_scopes = std::mem::take(&mut *self.captured_scopes.borrow_mut());
+        let mut values = vec![std::mem::take(&mut self.globals.borrow_mut().values)];
 
-            let environment = self.get(path.0.to_string());
+        for scope in captured_scopes.iter().filter_map(Weak::upgrade) {
+            values.push(std::mem::take(&mut scope.borrow_mut().values));
+        }
 
-            if let Value::ENVIRONMENT(target_environment) = environment {
-                target_environment.get(path.1.to_string())
-            } else if let Value::NULL = environment {
-                return Value::NULL;
-            } else {
-                let target = path.0;
-                self.error(&format!("{target} is not an environment."));
+        // Values are dropped after all scopes are cleared, so dropping them doesn't access a borrowed scope.
+        drop(values);
+    }
+
+    /// Stores data of the host, which native functions can access using user_data function. Data of the same type is replaced.
+    /// To mutate the data from native functions, wrap it in a type with interior mutability (like RefCell).
+    pub fn set_user_data<T: Any>(&mut self, data: T) {
+        self.user_data.insert(TypeId::of::<T>(), Rc::new(data));
+    }
+
+    /// Returns data of the host of given type, if it was set.
+    pub fn user_data<T: Any>(&self) -> Option<Rc<T>> {
+        self.user_data.get(&TypeId::of::<T>())
+            .and_then(|data| data.clone().downcast::<T>().ok())
+    }
+
+    /// Returns indices of arguments passed by name (name: value) to the native function being called. Other arguments may be
+    /// key values too, for example entries of a table. See Parameter::split_named.
+    pub fn named_argument_indices(&self) -> &[usize] {
+        &self.named_argument_indices
+    }
+
+    /// Sets the getter of properties of foreign objects of given type. If it returns a function, the property can be called as a method.
+    pub fn set_foreign_property_getter(&mut self, type_name: String, getter: ForeignPropertyGetter) {
+        self.foreign_properties.insert(type_name, getter);
+    }
+
+    /// Returns value of a property of a foreign object or null if its type has no property getter.
+    pub fn get_foreign_property(&mut self, object: &Value, property: &str) -> Result<Value, HierError> {
+        if let Value::FOREIGN(_, type_name) = object {
+            if let Some(getter) = self.foreign_properties.get(type_name).cloned() {
+                return getter(self, object, property);
             }
+        }
+
+        Ok(Value::NULL)
+    }
+
+    /// Writes text to the output sink (used by print functions).
+    pub fn write_output(&self, text: &str) -> Result<(), HierError> {
+        let mut output = self.output.borrow_mut();
+
+        output.write_all(text.as_bytes())
+            .and_then(|_| output.flush())
+            .map_err(|error| self.error(&format!("Failed to write output: {}.", error)))
+    }
+
+    /// Writes text to the error output sink (used by panics and warnings).
+    pub fn write_error_output(&self, text: &str) -> Result<(), HierError> {
+        let mut error_output = self.error_output.borrow_mut();
+
+        error_output.write_all(text.as_bytes())
+            .and_then(|_| error_output.flush())
+            .map_err(|error| self.error(&format!("Failed to write error output: {}.", error)))
+    }
+
+    pub fn begin_scope(&mut self) {
+        self.scope = Frame::new_scope(Some(self.scope.clone()));
+    }
+
+    pub fn end_scope(&mut self) -> Result<(), HierError> {
+        let enclosing = self.scope.borrow().enclosing.clone();
+
+        if let Some(enclosing) = enclosing {
+            self.scope = enclosing;
+            Ok(())
         } else {
-            if let Some(value) = self.values.get(&VariableId(self.scope, key.clone())) {
-                value.clone()
-            } else {
-                if self.scope == 0 {
-                    Value::NULL
-                } else {
-                    self.get_in_scope(key, self.scope - 1)
-                }
-            }
+            Err(self.error("Ended scope that didn't exist."))
+        }
+    }
+
+    /// Declares a variable in the outermost scope of the module.
+    pub fn declare_global(&mut self, key: String, value: Value) {
+        self.add_function_name(&key, &value);
+        self.globals.borrow_mut().insert(key, value);
+    }
+
+    /// Returns value of a variable declared in the scope at given depth (number of scopes enclosing the current one),
+    /// or none if it isn't declared there. Used with depths found by the resolver, which are verified this way.
+    pub fn get_at(&self, depth: usize, key: &str) -> Option<Value> {
+        let mut scope = self.scope.clone();
+
+        for _ in 0..depth {
+            let enclosing = scope.borrow().enclosing.clone()?;
+            scope = enclosing;
         }
+
+        let value = scope.borrow().get_own(key);
+        value
     }
 
-    fn get_in_scope(&self, key: String, scope: Scope) -> Value {
+    pub fn get(&self, key: String) -> Result<Value, HierError> {
         if key.contains('#') {
             // We can unwrap, because it is nil only if the delimiter is not present, but we can be sure, because we checked.
             let path = key.split_once("#").unwrap();
 
-            let environment = self.values.get(&VariableId(scope, key.clone()));
+            let environment = self.get(path.0.to_string())?;
 
-            if let Some(value) = environment {
-                if let Value::ENVIRONMENT(target_environment) = value {
-                    target_environment.get(key)
-                } else if let Value::NULL = value {
-                    return Value::NULL;
-                } else {
-                    let target = path.0;
-                    self.error(&format!("{target} is not an environment."));
-                }
+            if let Value::ENVIRONMENT(target_environment) = environment {
+                target_environment.get(path.1.to_string())
+            } else if let Value::NULL = environment {
+                Ok(Value::NULL)
             } else {
-                Value::NULL
+                let target = path.0;
+                Err(self.error(&format!("{target} is not an environment.")))
             }
         } else {
-            if let Some(value) = self.values.get(&VariableId(scope, key.clone())) {
-                value.clone()
-            } else {
-                if scope == 0 {
-                    Value::NULL
-                } else {
-                    self.get_in_scope(key, scope - 1)
-                }
-            }
+            Ok(self.scope.borrow().get(&key).unwrap_or(Value::NULL))
         }
     }
 
     /// Declare a new variable in current scope and assign it some value
-    pub fn declare(&mut self, key: String, value: Value) {
-        if self.is_in_repl {
-            self.values.insert(VariableId(self.scope, key), value);
-        } else {
-            if let Some(_) = self.values.get(&VariableId(self.scope, key.clone())) {
-                self.error(&format!("Variable '{}' already exists in current scope.", key));
-            } else {
-                self.values.insert(VariableId(self.scope, key), value);
-            }
+    pub fn declare(&mut self, key: String, value: Value) -> Result<(), HierError> {
+        self.add_function_name(&key, &value);
+
+        let mut scope = self.scope.borrow_mut();
+
+        if !self.is_in_repl && scope.values.contains_key(&key) {
+            return Err(self.error(&format!("Variable '{}' already exists in current scope.", key)));
         }
+
+        scope.insert(key, value);
+
+        Ok(())
     }
 
     /// Assign a value to a variable and error when it already exists
-    pub fn assign(&mut self, key: String, value: Value) {
-        if self.values.contains_key(&VariableId(self.scope, key.clone())) {
-            self.values.insert(VariableId(self.scope, key.clone()), value);
+    pub fn assign(&mut self, key: String, value: Value) -> Result<(), HierError> {
+        self.add_function_name(&key, &value);
+
+        if self.scope.borrow_mut().assign(&key, value) {
+            Ok(())
         } else {
-            if self.scope == 0 {
-                self.error(&format!("Variable {} doesn't exist.", key));
-            } else {
-                self.assign_in_scope(key, value, self.scope - 1)
-            }
+            Err(self.error(&format!("Variable {} doesn't exist.", key)))
         }
     }
 
-    fn assign_in_scope(&mut self, key: String, value: Value, scope: Scope) {
-        if self.values.contains_key(&VariableId(scope, key.clone())) {
-            self.values.insert(VariableId(scope, key.clone()), value);
-        } else {
-            if scope == 0 {
-                self.error(&format!("Variable {} doesn't exist.", key));
-            } else {
-                self.assign_in_scope(key, value, scope - 1);
-            }
+    fn add_function_name(&mut self, key: &str, value: &Value) {
+        if matches!(value, Value::FUNCTION(_, _, _) | Value::NATIVE_FUNCTION(_, _)) && !self.function_names.contains(key) {
+            self.function_names.insert(key.to_string());
         }
     }
 
-    fn call_user_defined_function(&mut self, name: &String, arguments: Vec<Value>) -> Value {
-        if let Value::FUNCTION(parameters, block) = self.get(name.clone().to_string()) {
-            if arguments.len() != parameters.len() {
-                self.error(&format!("Function {} expects {} arguments, but {} were provided.", name, parameters.len(), arguments.len()));
-            }
+    fn call_user_defined_function(&mut self, name: &String, arguments: Vec<Value>, named: &[usize]) -> Result<Value, HierError> {
+        let function = self.get(name.clone())?;
 
-            {
-                self.begin_scope();
+        self.call_function_value_with_named(name, function, arguments, named)
+    }
 
-                for (i, argument) in arguments.iter().enumerate() {
-                    self.declare(parameters[i].clone(), argument.clone());
-                }
+    /// Calls a function value (user defined or native). Name is used only in messages.
+    pub fn call_function_value(&mut self, name: &String, function: Value, arguments: Vec<Value>) -> Result<Value, HierError> {
+        self.call_function_value_with_named(name, function, arguments, &[])
+    }
 
-                if let Value::BLOCK(block) = (*block).clone() {
-                    let value = self.interpret_block(block);
-                    self.end_scope();
-                    value
-                } else {
-                    Value::NULL
-                }
+    /// Calls a function value with arguments at given indices passed by name (key values). User defined functions bind them to
+    /// parameters with the same names, native functions receive them as key values and their indices from named_argument_indices.
+    pub fn call_function_value_with_named(&mut self, name: &String, function: Value, arguments: Vec<Value>, named: &[usize]) -> Result<Value, HierError> {
+        if let Value::FUNCTION(parameters, block, closure) = function {
+            let Some(closure) = closure.scope() else {
+                return Err(self.error(&format!("Scope of function {} no longer exists.", name)));
+            };
+
+            let arguments = Parameter::bind(name, parameters, arguments, named).map_err(|error| self.error(&error))?;
+
+            // Function body is run in a scope enclosed by the one it was defined in, not the one it is called from.
+            let caller_scope = std::mem::replace(&mut self.scope, Frame::new_scope(Some(closure)));
+
+            // Scope is restored even if the function fails, so the environment can be used after the error.
+            let result = self.call_block_with_arguments(arguments, *block);
+            self.scope = caller_scope;
+
+            match self.signal.take() {
+                Some(Signal::RETURN(value)) if result.is_ok() => Ok(value),
+                Some(signal) if result.is_ok() => Err(self.error(&signal.error_message())),
+                _ => result
             }
-        } else if let Value::NATIVE_FUNCTION(function, arity) = self.get(name.clone().to_string()) {
+        } else if let Value::NATIVE_FUNCTION(function, arity) = function {
             if arity != -1 && arguments.len() != arity as usize {
-                self.error(&format!("Function {} expects {} arguments, but {} were provided.", name, arity, arguments.len()));
+                return Err(self.error(&format!("Function {} expects {} arguments, but {} were provided.", name, arity, arguments.len())));
             }
 
-            function(self, arguments)
+            let caller_indices = std::mem::replace(&mut self.named_argument_indices, named.to_vec());
+            let result = function(self, arguments);
+            self.named_argument_indices = caller_indices;
+
+            result
         } else {
-            warning(&format!("Function {} doesn't exist or is not a function.", name));
-            Value::NULL
+            self.warning(&format!("Function {} doesn't exist or is not a function.", name));
+            Ok(Value::NULL)
         }
     }
 
-    pub fn call_function(&mut self, name: &String, arguments: Vec<Value>) -> Value {
+    fn call_block_with_arguments(&mut self, arguments: Vec<(String, Value)>, block: Value) -> Result<Value, HierError> {
+        for (name, argument) in arguments {
+            self.declare(name, argument)?;
+        }
+
+        if let Value::BLOCK(block) = block {
+            self.interpret_block(block)
+        } else {
+            Ok(Value::NULL)
+        }
+    }
+
+    pub fn call_function(&mut self, name: &String, arguments: Vec<Value>) -> Result<Value, HierError> {
+        self.call_function_with_named(name, arguments, &[])
+    }
+
+    /// Calls a function with arguments at given indices passed by name (see call_function_value_with_named).
+    pub fn call_function_with_named(&mut self, name: &String, arguments: Vec<Value>, named: &[usize]) -> Result<Value, HierError> {
         if name.contains('#') {
             // We can unwrap, because it is nil only if the delimiter is not present, but we can be sure, because we checked.
-            let path = name.split_once("#").unwrap();
+            let path = name.split_once('#').unwrap();
 
-            let environment = self.get(path.0.to_string());
+            let environment = self.get(path.0.to_string())?;
 
             if let Value::ENVIRONMENT(target_environment) = environment {
                 let mut environment = target_environment.clone();
 
-                let result = environment.call_function(&path.1.to_string(), arguments);
+                let result = environment.call_function_with_named(&path.1.to_string(), arguments, named);
 
-                self.assign(path.0.to_string(), Value::ENVIRONMENT(environment));
+                self.assign(path.0.to_string(), Value::ENVIRONMENT(environment))?;
 
                 result
             } else if let Value::NULL = environment {
-                return Value::NULL;
+                Ok(Value::NULL)
             } else {
                 let target = path.0;
-                self.error(&format!("{target} is not an environment."));
+                Err(self.error(&format!("{target} is not an environment.")))
             }
         } else {
-            match &name as &str {
-                "get" => self.call_get(arguments),
+            // Functions declared in the code or by the host shadow built-in functions with the same name. Only names that
+            // were given a function are looked up, so calls of built-in functions don't search all scopes.
+            if self.function_names.contains(name) {
+                let function = self.scope.borrow().get_function(name);
+
+                if let Some(function) = function {
+                    return self.call_function_value_with_named(name, function, arguments, named);
+                }
+            }
+
+            match name as &str {
+                "get" | GET_FUNCTION => self.call_get(arguments),
                 "import" => self.call_import(arguments),
                 "&" | "list" => self.call_list(arguments),
                 "+" => self.call_addition(arguments),
@@ -248,12 +532,30 @@ impl Environment {
                 "println" => self.call_println(arguments),
                 "eval" => self.call_eval(arguments),
                 "break" => self.call_break(arguments),
+                "continue" => self.call_continue(arguments),
+                "return" => self.call_return(arguments),
                 "error" => self.call_error(arguments),
                 "panic" => self.call_panic(arguments),
                 "read" => self.call_read(arguments),
                 "insert" => self.call_insert(arguments),
                 "round" => self.call_round(arguments),
                 "map" => self.call_map(arguments),
+                "filter" => self.call_filter(arguments),
+                "find" => self.call_find(arguments),
+                "any" => self.call_any(false, arguments),
+                "all" => self.call_any(true, arguments),
+                "reduce" | "fold" => self.call_reduce(arguments),
+                "sort" => self.call_sort(arguments),
+                "sort_by" => self.call_sort_by(arguments),
+                "zip" => self.call_zip(arguments),
+                "enumerate" => self.call_enumerate(arguments),
+                "flatten" => self.call_flatten(arguments),
+                "reverse" => self.call_reverse(arguments),
+                "unique" => self.call_unique(arguments),
+                "slice" => self.call_slice(arguments),
+                "concat" => self.call_concat(arguments),
+                "index_of" => self.call_index_of(arguments),
+                "contains" => self.call_contains(arguments),
                 "remove" => self.call_remove(arguments),
                 "replace" => self.call_replace(arguments),
                 "length" => self.call_length(arguments),
@@ -262,6 +564,13 @@ impl Environment {
                 "if" => self.call_if(arguments),
                 "while" => self.call_while(arguments),
                 "table" | "#" => self.call_table(arguments),
+                "set" | SET_FUNCTION => self.call_set(arguments),
+                "delete" => self.call_delete(arguments),
+                "has" => self.call_has(arguments),
+                "keys" => self.call_keys(arguments),
+                "values" => self.call_values(arguments),
+                "entries" => self.call_entries(arguments),
+                "merge" => self.call_merge(arguments),
                 "repeat" => self.call_repeat(arguments),
                 "for" => self.call_for(arguments),
                 "run" => self.call_run(arguments),
@@ -270,63 +579,65 @@ impl Environment {
                     if name.chars().nth(0).unwrap_or(' ') == '@' {
                         // Process declaration
                         if name == "@" {
-                            self.error("Name can't be empty (can't be only @).")
+                            return Err(self.error("Name can't be empty (can't be only @)."));
                         }
 
                         let mut name = name.clone();
                         name.remove(0);
 
                         if 2 < arguments.len() {
-                            self.declare(name, Value::LIST(arguments.clone()));
-                            Value::LIST(arguments)
+                            self.declare(name, Value::LIST(arguments.clone()))?;
+                            Ok(Value::LIST(arguments))
                         } else if arguments.len() == 2 {
                             if let Value::FUNCTION_ARGUMENTS(parameters) = arguments[0].clone() {
                                 if let Value::BLOCK(block) = arguments[1].clone() {
-                                    self.declare(name, Value::FUNCTION(parameters.clone(), Box::new(Value::BLOCK(block.clone()))));
-                                    Value::FUNCTION(parameters, Box::new(Value::BLOCK(block)))
+                                    let function = Value::FUNCTION(parameters, Box::new(Value::BLOCK(block)), self.closure());
+                                    self.declare(name, function.clone())?;
+                                    Ok(function)
                                 } else {
-                                    self.error("Function definition's second argument must be a block.");
+                                    Err(self.error("Function definition's second argument must be a block."))
                                 }
                             } else {
-                                self.error("Function definition's first argument must be function arguments.");
+                                Err(self.error("Function definition's first argument must be function arguments."))
                             }
                         } else if arguments.len() == 1 {
-                            self.declare(name, arguments[0].clone());
-                            arguments[0].clone()
+                            self.declare(name, arguments[0].clone())?;
+                            Ok(arguments[0].clone())
                         } else {
-                            self.error("Variable set operation must have 1 or more arguments.");
+                            Err(self.error("Variable set operation must have 1 or more arguments."))
                         }
                     } else if name.chars().nth(0).unwrap_or(' ') == '=' {
                         // Process assignment
                         if name == "=" {
-                            self.error("Name can't be empty (can't be only =).")
+                            return Err(self.error("Name can't be empty (can't be only =)."));
                         }
 
                         let mut name = name.clone();
                         name.remove(0);
 
                         if 2 < arguments.len() {
-                            self.assign(name, Value::LIST(arguments.clone()));
-                            Value::LIST(arguments)
+                            self.assign(name, Value::LIST(arguments.clone()))?;
+                            Ok(Value::LIST(arguments))
                         } else if arguments.len() == 2 {
                             if let Value::FUNCTION_ARGUMENTS(parameters) = arguments[0].clone() {
                                 if let Value::BLOCK(block) = arguments[1].clone() {
-                                    self.assign(name, Value::FUNCTION(parameters.clone(), Box::new(Value::BLOCK(block.clone()))));
-                                    Value::FUNCTION(parameters, Box::new(Value::BLOCK(block)))
+                                    let function = Value::FUNCTION(parameters, Box::new(Value::BLOCK(block)), self.closure());
+                                    self.assign(name, function.clone())?;
+                                    Ok(function)
                                 } else {
-                                    self.error("Function definition's second argument must be a block.");
+                                    Err(self.error("Function definition's second argument must be a block."))
                                 }
                             } else {
-                                self.error("Function definition's first argument must be function arguments.");
+                                Err(self.error("Function definition's first argument must be function arguments."))
                             }
                         } else if arguments.len() == 1 {
-                            self.assign(name, arguments[0].clone());
-                            arguments[0].clone()
+                            self.assign(name, arguments[0].clone())?;
+                            Ok(arguments[0].clone())
                         } else {
-                            self.error("Variable set operation must have 1 or more arguments.");
+                            Err(self.error("Variable set operation must have 1 or more arguments."))
                         }
                     } else {
-                        self.call_user_defined_function(name, arguments)
+                        self.call_user_defined_function(name, arguments, named)
                     }
                 }
             }
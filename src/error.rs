@@ -0,0 +1,48 @@
+use std::fmt::{Display, Formatter};
+use crate::location::Location;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ErrorKind {
+    LEX,
+    PARSE,
+    RESOLVE,
+    RUNTIME
+}
+
+impl ErrorKind {
+    pub fn text_representation(&self) -> String {
+        match self {
+            ErrorKind::LEX => "Lex".to_string(),
+            ErrorKind::PARSE => "Parse".to_string(),
+            ErrorKind::RESOLVE => "Resolve".to_string(),
+            ErrorKind::RUNTIME => "Runtime".to_string()
+        }
+    }
+}
+
+/// Error that stopped tokenizing, parsing or interpreting of the code.
+#[derive(Debug, Clone)]
+pub struct HierError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub location: Location
+}
+
+impl HierError {
+    pub fn new(kind: ErrorKind, message: String, location: Location) -> Self {
+        Self {
+            kind,
+            message,
+            location
+        }
+    }
+}
+
+impl Display for HierError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}:{} in {}) {} error: {}", self.location.line_number, self.location.offset, self.location.module, self.kind.text_representation(), self.message)
+    }
+}
+
+impl std::error::Error for HierError {}
\ No newline at end of file
@@ -1,3 +1,8 @@
+use std::cell::OnceCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::bytecode::Chunk;
+use crate::compiler::Compiler;
 use crate::interpolated_string::InterpolatedString;
 use crate::location::Location;
 use crate::value::Value;
@@ -7,9 +12,10 @@ use crate::value::Value;
 pub enum Expression {
     STRING(InterpolatedString, Location),
     NUMBER(f64, Location),
-    IDENTIFIER(String, Location),
+    /// Identifier with depth of the scope declaring it (from the resolver), if it is known.
+    IDENTIFIER(String, Location, Option<usize>),
     LIST(Vec<Expression>, Location),
-    BLOCK(Vec<Expression>, Location),
+    BLOCK(Rc<Block>, Location),
     PROPERTY(Box<Expression>, String, Location),
     VALUE(Value),
     KEY_VALUE(String, Box<Expression>, Location)
@@ -20,7 +26,7 @@ impl Expression {
         match self {
             Expression::STRING(_, location) => location.clone(),
             Expression::NUMBER(_, location) => location.clone(),
-            Expression::IDENTIFIER(_, location) => location.clone(),
+            Expression::IDENTIFIER(_, location, _) => location.clone(),
             Expression::LIST(_, location) => location.clone(),
             Expression::BLOCK(_, location) => location.clone(),
             Expression::PROPERTY(_, _, location) => location.clone(),
@@ -28,4 +34,37 @@ impl Expression {
             Expression::KEY_VALUE(_, _, location) => location.clone()
         }
     }
+}
+
+/// Block of code. It is shared by values created from it, so it is compiled to bytecode only once.
+#[derive(Clone)]
+pub struct Block {
+    pub expressions: Vec<Expression>,
+    chunk: OnceCell<Rc<Chunk>>
+}
+
+impl Block {
+    pub fn new(expressions: Vec<Expression>) -> Self {
+        Self {
+            expressions,
+            chunk: OnceCell::new()
+        }
+    }
+
+    /// Returns expressions for modifying them, which discards the compiled bytecode.
+    pub fn expressions_mut(&mut self) -> &mut Vec<Expression> {
+        self.chunk = OnceCell::new();
+        &mut self.expressions
+    }
+
+    /// Returns the block compiled to bytecode, compiling it on first use.
+    pub fn chunk(&self) -> Rc<Chunk> {
+        self.chunk.get_or_init(|| Rc::new(Compiler::new().compile_block(&self.expressions))).clone()
+    }
+}
+
+impl Debug for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.expressions)
+    }
 }
\ No newline at end of file
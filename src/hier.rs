@@ -1,24 +1,74 @@
-use crate::environment::{Environment, VariableId};
+use std::any::Any;
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+use crate::conversion::TypedFunction;
+use crate::diagnostic::Diagnostic;
+use crate::environment::{Environment, ExitHandler};
+use crate::error::{ErrorKind, HierError};
+use crate::input::LineReader;
 use crate::parser::Parser;
+use crate::report;
+use crate::resolver::Resolver;
 use crate::tokenizer::Tokenizer;
 use crate::value::Value;
+use crate::vm::Backend;
 
 pub struct Hier {
     environment: Environment,
-    module_reader: fn(String) -> String,
-    exit_handler: fn() -> !
+    diagnostics: Vec<Diagnostic>
+}
+
+/// Clears scopes of the instance, so functions and scopes that refer to each other don't keep their values alive.
+impl Drop for Hier {
+    fn drop(&mut self) {
+        self.environment.clear_scopes();
+    }
 }
 
 impl Hier {
-    pub fn new(path: String, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> Self {
+    /// Creates a new instance. Module reader reads contents of imported modules. Exit handler is called when the code panics
+    /// (its result is ignored) and if it returns, the panic is returned as a runtime error.
+    pub fn new<R: 'static>(path: String, module_reader: impl Fn(String) -> String + 'static, exit_handler: impl Fn() -> R + 'static) -> Self {
+        let exit_handler: ExitHandler = Rc::new(move || { exit_handler(); });
+
+        Self::from_environment(Environment::new(false, path, Rc::new(module_reader), exit_handler))
+    }
+
+    pub(crate) fn from_environment(environment: Environment) -> Self {
         Self {
-            environment: Environment::new(false, path, module_reader, exit_handler),
-            module_reader,
-            exit_handler
+            environment,
+            diagnostics: vec![]
         }
     }
 
-    pub fn run(&mut self, code: String) -> Value {
+    /// Runs the code and reports all diagnostics (including the error that stopped it) before returning the result.
+    pub fn run(&mut self, code: String) -> Result<Value, HierError> {
+        let result = self.try_run(code);
+
+        for diagnostic in &self.diagnostics {
+            report(&self.environment.error_output, diagnostic);
+        }
+
+        if let Err(error) = &result {
+            if error.kind == ErrorKind::RUNTIME {
+                report(&self.environment.error_output, &Diagnostic::from(error.clone()));
+            }
+        }
+
+        result
+    }
+
+    /// Runs the code without reporting anything. Diagnostics of the run can be read using diagnostics function.
+    pub fn try_run(&mut self, code: String) -> Result<Value, HierError> {
+        self.diagnostics.clear();
+
+        Self::run_in(&mut self.environment, code, &mut self.diagnostics)
+    }
+
+    /// Runs the code in the environment, adding its diagnostics to given ones. Used by eval, whose environment must not be cleared
+    /// like the one of a dropped instance, because it shares captured scopes with the calling module.
+    pub(crate) fn run_in(environment: &mut Environment, code: String, diagnostics: &mut Vec<Diagnostic>) -> Result<Value, HierError> {
         let mut code = code;
 
         if !code.starts_with('(') {
@@ -28,31 +78,119 @@ impl Hier {
 
         let mut tokenizer = Tokenizer::new(code);
 
-        if tokenizer.tokenize_module() {
-            println!("Failed.");
-            (self.exit_handler)();
+        tokenizer.tokenize_module();
+        diagnostics.append(&mut tokenizer.diagnostics);
+
+        if let Some(error) = Diagnostic::first_error(diagnostics, ErrorKind::LEX) {
+            return Err(error);
+        }
+
+        let mut parser = Parser::with_spans(tokenizer.tokens, tokenizer.spans);
+
+        parser.parse();
+        diagnostics.append(&mut parser.diagnostics);
+
+        if let Some(error) = Diagnostic::first_error(diagnostics, ErrorKind::PARSE) {
+            return Err(error);
         }
 
-        let mut parser = Parser::new(tokenizer.tokens, self.module_reader, self.exit_handler);
+        let mut resolver = Resolver::new(environment.is_in_repl());
+
+        resolver.resolve(&mut parser.code);
+        let error = Diagnostic::first_error(&resolver.diagnostics, ErrorKind::RESOLVE);
+        diagnostics.append(&mut resolver.diagnostics);
 
-        if parser.parse() {
-            println!("Failed.");
-            (self.exit_handler)();
+        if let Some(error) = error {
+            return Err(error);
         }
 
-        self.environment.code = parser.code;
-        self.environment.interpret()
+        environment.code = parser.code;
+        let result = environment.interpret();
+        diagnostics.append(&mut environment.diagnostics);
+
+        result
+    }
+
+    /// Calls a function (defined by the code, added by the host or built-in) with given arguments.
+    /// Diagnostics of the call replace those of the last run.
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let result = self.environment.call_function(&name.to_string(), arguments);
+        self.diagnostics = std::mem::take(&mut self.environment.diagnostics);
+
+        result
+    }
+
+    /// Calls a function value (for example, a function passed by the code to a native function) with given arguments.
+    pub fn call_value(&mut self, function: &Value, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let result = self.environment.call_function_value(&function.text_representation(), function.clone(), arguments);
+        self.diagnostics = std::mem::take(&mut self.environment.diagnostics);
+
+        result
     }
 
-    pub fn add_function(&mut self, name: String, arguments_count: i64, function: fn(&mut Environment, Vec<Value>) -> Value) {
+    /// Returns value of a global variable or null if it doesn't exist. Variables of imported modules can be accessed using module#name.
+    pub fn get_variable(&self, name: &str) -> Result<Value, HierError> {
+        self.environment.get(name.to_string())
+    }
+
+    /// Diagnostics (errors and warnings) collected during the last run.
+    pub fn diagnostics(&self) -> &Vec<Diagnostic> {
+        &self.diagnostics
+    }
+
+    /// Sets the writer that print functions write to (stdout by default).
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.environment.output = Rc::new(RefCell::new(output));
+    }
+
+    /// Sets the writer that panics and reported diagnostics are written to (stderr by default).
+    pub fn set_error_output(&mut self, error_output: impl Write + 'static) {
+        self.environment.error_output = Rc::new(RefCell::new(error_output));
+    }
+
+    /// Sets the source of lines for read function (stdin by default).
+    pub fn set_input(&mut self, input: impl LineReader + 'static) {
+        self.environment.input = Rc::new(RefCell::new(input));
+    }
+
+    /// Sets the backend running the code (tree walker by default, or the VM if the vm feature is enabled).
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.environment.backend = backend;
+    }
+
+    /// Stores data of the host, which native functions can access using Environment's user_data function.
+    pub fn set_user_data<T: Any>(&mut self, data: T) {
+        self.environment.set_user_data(data);
+    }
+
+    pub fn user_data<T: Any>(&self) -> Option<Rc<T>> {
+        self.environment.user_data()
+    }
+
+    /// Adds a function implemented in Rust. The function can be a closure capturing state of the host.
+    pub fn add_function(&mut self, name: String, arguments_count: i64, function: impl Fn(&mut Environment, Vec<Value>) -> Result<Value, HierError> + 'static) {
         if arguments_count < -1 {
             panic!("Invalid argument count for function {}. Must be either -1 (infinite) or 0 and higher.", name);
         }
 
-        self.environment.values.insert(VariableId(0, name), Value::NATIVE_FUNCTION(function, arguments_count));
+        self.environment.declare_global(name, Value::NATIVE_FUNCTION(Rc::new(function), arguments_count));
+    }
+
+    /// Adds a function implemented in Rust with arguments and result converted from and to values, for example |a: f64, b: f64| a + b.
+    /// Arity and types of arguments are checked before calling it.
+    pub fn add_typed_function<Arguments, F: TypedFunction<Arguments>>(&mut self, name: String, function: F) {
+        let function = function.into_native_function(name.clone());
+
+        self.environment.declare_global(name, Value::NATIVE_FUNCTION(function, F::arity()));
+    }
+
+    /// Adds a getter of properties of foreign objects of given type. If it returns a function, the property can be called as a method,
+    /// with the object as first argument: (object.method arguments).
+    pub fn add_foreign_property_getter(&mut self, type_name: String, getter: impl Fn(&mut Environment, &Value, &str) -> Result<Value, HierError> + 'static) {
+        self.environment.set_foreign_property_getter(type_name, Rc::new(getter));
     }
 
     pub fn add_variable(&mut self, name: String, value: Value) {
-        self.environment.values.insert(VariableId(0, name), value);
+        self.environment.declare_global(name, value);
     }
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io;
+use std::rc::Rc;
+
+/// Source of lines for the read function.
+pub trait LineReader {
+    /// Reads the next line without its line terminator. Returns None when there is no more input.
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+}
+
+/// Line reader shared by environments.
+pub type Source = Rc<RefCell<dyn LineReader>>;
+
+pub fn stdin_source() -> Source {
+    Rc::new(RefCell::new(StdinReader))
+}
+
+pub struct StdinReader;
+
+impl LineReader for StdinReader {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+
+        if io::stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(Some(line))
+    }
+}
+
+/// Reader returning prepared lines, useful for driving scripts in tests.
+#[derive(Clone, Default)]
+pub struct ScriptedInput {
+    lines: VecDeque<String>
+}
+
+impl ScriptedInput {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines: VecDeque::from(lines)
+        }
+    }
+
+    pub fn push_line(&mut self, line: String) {
+        self.lines.push_back(line);
+    }
+}
+
+impl LineReader for ScriptedInput {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.lines.pop_front())
+    }
+}
+
+/// Allows using closures (for example, asking for input in a GUI) as line readers.
+impl<F> LineReader for F where F: FnMut() -> io::Result<Option<String>> {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        self()
+    }
+}
\ No newline at end of file
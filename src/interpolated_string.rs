@@ -1,76 +1,88 @@
-use crate::expression::Expression;
-use crate::{Location, report};
+use crate::diagnostic::Diagnostic;
+use std::rc::Rc;
+use crate::expression::Block;
+use crate::location::{Location, Span};
 use crate::environment::Environment;
+use crate::error::HierError;
 use crate::parser::Parser;
 use crate::tokenizer::Tokenizer;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 enum InterpolatedStringPart {
     RAW(String),
-    EXPRESSION(Expression)
+    EXPRESSION(Rc<Block>)
 }
 
 #[derive(Debug, Clone)]
 pub struct InterpolatedString {
     parts: Vec<InterpolatedStringPart>,
     raw: String,
-    current_index: usize,
-    pub module_reader: fn(String) -> String,
-    pub exit_handler: fn() -> !
-
+    current_index: usize
 }
 
 impl InterpolatedString {
-    pub fn construct(string: String, location: Location, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> InterpolatedString {
-        let mut string = Self::new(string, module_reader, exit_handler);
+    /// Creates and parses the string, returning it with diagnostics found in it.
+    pub fn construct(string: String, span: Span) -> (InterpolatedString, Vec<Diagnostic>) {
+        let mut string = Self::new(string);
 
-        string.parse(location);
+        let diagnostics = string.parse(&span);
 
-        string
+        (string, diagnostics)
     }
 
-    pub fn new(string: String, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> Self {
+    pub fn new(string: String) -> Self {
         Self {
             parts: vec![],
             raw: string,
-            current_index: 0,
-            module_reader,
-            exit_handler
+            current_index: 0
         }
     }
 
-    pub fn parse(&mut self, location: Location) {
+    /// Parses escape sequences and interpolations. Span is the span of the string token, used to locate the diagnostics.
+    pub fn parse(&mut self, span: &Span) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
         let mut will_interpolate = false;
         let mut raw_part = String::new();
 
-        while self.current_index < self.raw.len() {
+        while self.current_index < self.raw.chars().count() {
             let current_char = self.peek();
 
-            if current_char == '\\' {
-                will_interpolate = true;
-                self.consume();
-            } else if will_interpolate && current_char == '(' {
+            if will_interpolate && current_char == '(' {
+                will_interpolate = false;
+
                 self.parts.push(InterpolatedStringPart::RAW(raw_part));
                 raw_part = String::new();
 
-                let mut tokenizer = Tokenizer::new(self.raw[self.current_index..].to_string());
+                let mut tokenizer = Tokenizer::new_at(self.raw.chars().skip(self.current_index).collect(), &self.location_at(span, self.current_index));
 
                 let offset = tokenizer.tokenize_interpolation();
                 self.current_index += offset;
+                diagnostics.append(&mut tokenizer.diagnostics);
 
-                let mut parser = Parser::new(tokenizer.tokens, self.module_reader, self.exit_handler);
+                let mut parser = Parser::with_spans(tokenizer.tokens, tokenizer.spans);
 
                 parser.parse();
+                diagnostics.append(&mut parser.diagnostics);
 
-                self.parts.push(InterpolatedStringPart::EXPRESSION(parser.code))
+                self.parts.push(InterpolatedStringPart::EXPRESSION(Rc::new(Block::new(vec![parser.code]))))
             } else if will_interpolate {
+                will_interpolate = false;
+
                 match current_char {
                     'n' => raw_part.push('\n'),
                     't' => raw_part.push('\t'),
                     '0' => raw_part.push('\0'),
-                    _ => report(&("Invalid escape sequence: \\".to_string() + &(current_char.to_string())), location.clone())
+                    '\\' => raw_part.push('\\'),
+                    _ => {
+                        let escape = Span::new(self.location_at(span, self.current_index - 1), self.location_at(span, self.current_index));
+                        diagnostics.push(Diagnostic::error("Invalid escape sequence: \\".to_string() + &(current_char.to_string()), escape))
+                    }
                 }
 
+                self.consume();
+            } else if current_char == '\\' {
+                will_interpolate = true;
                 self.consume();
             } else {
                 raw_part.push(current_char);
@@ -81,6 +93,25 @@ impl InterpolatedString {
         if !raw_part.is_empty() {
             self.parts.push(InterpolatedStringPart::RAW(raw_part));
         }
+
+        diagnostics
+    }
+
+    /// Location of the character at the index, counted from the opening quote of the string token.
+    fn location_at(&self, span: &Span, index: usize) -> Location {
+        let mut line_number = span.start.line_number;
+        let mut offset = span.start.offset + 1;
+
+        for char in self.raw.chars().take(index) {
+            if char == '\n' {
+                line_number += 1;
+                offset = 1;
+            } else {
+                offset += 1;
+            }
+        }
+
+        Location::new(span.start.module.clone(), line_number, offset)
     }
 
     fn peek(&self) -> char {
@@ -93,16 +124,20 @@ impl InterpolatedString {
         char
     }
 
-    pub fn resolve(&self, environment: &mut Environment) -> String {
+    pub fn resolve(&self, environment: &mut Environment) -> Result<String, HierError> {
         let mut resolved = String::new();
 
-        for part in self.parts.clone() {
+        for part in &self.parts {
             match part {
-                InterpolatedStringPart::RAW(raw) => resolved.push_str(&raw),
-                InterpolatedStringPart::EXPRESSION(expression) => resolved.push_str(&environment.interpret_block(vec![expression]).text_representation())
+                InterpolatedStringPart::RAW(raw) => resolved.push_str(raw),
+                InterpolatedStringPart::EXPRESSION(block) => resolved.push_str(&environment.interpret_block(block.clone())?.text_representation())
+            }
+
+            if environment.signal.is_some() {
+                break;
             }
         }
 
-        resolved
+        Ok(resolved)
     }
 }
\ No newline at end of file
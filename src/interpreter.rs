@@ -1,45 +1,74 @@
+use std::rc::Rc;
+use crate::compiler::Compiler;
+use crate::diagnostic::Diagnostic;
 use crate::environment::Environment;
-use crate::expression::Expression;
+use crate::error::HierError;
+use crate::expression::{Block, Expression};
+use crate::location::Span;
+use crate::parameter::Parameter;
+use crate::parser::GET_FUNCTION;
+use crate::signal::Signal;
 use crate::types::Type;
 use crate::value::Value;
-
-pub fn warning(error: &str) {
-    eprintln!("?: {}", error);
-}
+use crate::vm::Backend;
 
 impl Environment {
-    pub fn interpret(&mut self) -> Value {
-        if let Expression::BLOCK(block, _) = self.code.clone() {
+    /// Records a warning at the location that is currently being interpreted.
+    pub fn warning(&mut self, warning: &str) {
+        self.diagnostics.push(Diagnostic::warning(warning.to_string(), Span::at(self.current_interpreting_location.clone())));
+    }
+
+    pub fn interpret(&mut self) -> Result<Value, HierError> {
+        let scope = self.scope.clone();
+
+        let result = if let Expression::BLOCK(block, _) = self.code.clone() {
             self.interpret_block(block)
+        } else if self.backend == Backend::VM {
+            let chunk = Compiler::new().compile_expression(&self.code);
+            self.run_chunk(&chunk)
         } else {
             self.visit(self.code.clone())
+        };
+
+        // Scopes left by the error are dropped, so the environment can be used after it.
+        if result.is_err() {
+            self.scope = scope;
+        }
+
+        // Return outside of functions returns from the module.
+        match self.signal.take() {
+            Some(Signal::RETURN(value)) if result.is_ok() => Ok(value),
+            Some(signal) if result.is_ok() => Err(self.error(&signal.error_message())),
+            _ => result
         }
     }
 
-    pub fn interpret_block(&mut self, block: Vec<Expression>) -> Value {
+    pub fn interpret_block(&mut self, block: Rc<Block>) -> Result<Value, HierError> {
+        if self.backend == Backend::VM {
+            return self.run_chunk(&block.chunk());
+        }
+
         let mut last_result = Value::NULL;
 
-        for expression in block {
-            last_result = self.visit(expression);
+        for expression in &block.expressions {
+            last_result = self.visit(expression.clone())?;
 
-            if let Value::ERROR(error_message) = last_result.clone() {
-                if error_message == "LoopExit".to_string() {
-                    break;
-                }
+            if self.signal.is_some() {
+                break;
             }
         }
 
-        last_result
+        Ok(last_result)
     }
 
-    pub fn visit(&mut self, value: Expression) -> Value {
+    pub fn visit(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
         match value {
             Expression::STRING(_, _) => self.visit_string(value),
             Expression::VALUE(_) => self.visit_value(value),
             Expression::NUMBER(_, _) => self.visit_number(value),
-            Expression::IDENTIFIER(_, _) => self.visit_identifier(value),
+            Expression::IDENTIFIER(_, _, _) => self.visit_identifier(value),
             Expression::LIST(_, _) => self.visit_list(value),
             Expression::BLOCK(_, _) => self.visit_block(value),
             Expression::KEY_VALUE(_, _, _) => self.visit_key_value(value),
@@ -47,51 +76,62 @@ impl Environment {
         }
     }
 
-    pub fn visit_property(&mut self, property: Expression) -> Value {
+    pub fn visit_property(&mut self, property: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = property.get_location().clone();
 
         if let Expression::PROPERTY(expression, identifier, _) = property {
-            let argument = self.visit(*(expression.clone()));
-            self.call_function(&("get".to_string()), vec![argument, Value::STRING(identifier)])
+            let argument = self.visit(*expression)?;
+
+            if self.signal.is_some() {
+                return Ok(Value::NULL);
+            }
+
+            self.call_function(&GET_FUNCTION.to_string(), vec![argument, Value::STRING(identifier)])
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_list(&mut self, list: Expression) -> Value {
+    pub fn visit_list(&mut self, list: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = list.get_location().clone();
 
         if let Expression::LIST(list, _) = list {
-            if list.len() == 0 {
-                Value::NULL
+            if list.is_empty() {
+                Ok(Value::NULL)
             } else {
-                if let Expression::IDENTIFIER(name, _) = &list[0] {
+                if let Expression::IDENTIFIER(name, _, _) = &list[0] {
                     if name == "|" {
-                        let mut expressions = list.clone();
-                        expressions.remove(0);
+                        let mut parameters: Vec<Parameter> = Vec::new();
 
-                        let mut arguments: Vec<String> = Vec::new();
+                        for (mut parameter, default) in Parameter::parse(&list[1..]).map_err(|error| self.error(&error))? {
+                            if let Some(default) = default {
+                                parameter.default = Some(self.visit(default.clone())?);
 
-                        for expression in expressions {
-                            if let Expression::IDENTIFIER(argument, _) = expression {
-                                arguments.push(argument);
-                            } else {
-                                self.error("Function arguments must be identifiers.");
+                                if self.signal.is_some() {
+                                    return Ok(Value::NULL);
+                                }
                             }
+
+                            parameters.push(parameter);
                         }
 
-                        Value::FUNCTION_ARGUMENTS(arguments)
+                        Ok(Value::FUNCTION_ARGUMENTS(parameters))
                     } else {
                         let mut expressions = list.clone();
                         expressions.remove(0);
 
                         let mut values: Vec<Value> = Vec::new();
+                        let named = Self::named_arguments(&expressions);
 
                         for expression in expressions {
-                            values.push(self.visit(expression));
+                            values.push(self.visit(expression)?);
+
+                            if self.signal.is_some() {
+                                return Ok(Value::NULL);
+                            }
                         }
 
-                        self.call_function(&name, values)
+                        self.call_function_with_named(name, values, &named)
                     }
                 } else if let Expression::PROPERTY(expression, identifier, _) = &list[0] {
                     let mut expressions = list.clone();
@@ -99,106 +139,150 @@ impl Environment {
                     expressions.insert(0, (**expression).clone());
 
                     let mut values: Vec<Value> = Vec::new();
+                    let named = Self::named_arguments(&expressions);
 
                     for expression in expressions {
-                        values.push(self.visit(expression));
+                        values.push(self.visit(expression)?);
+
+                        if self.signal.is_some() {
+                            return Ok(Value::NULL);
+                        }
                     }
 
-                    self.call_function(identifier, values)
+                    self.call_method(identifier, values, &named)
                 } else {
-                    if let Value::FUNCTION_ARGUMENTS(arguments) = self.visit(list[0].clone()) {
-                        if list.len() != 2 {
-                            self.error("Anonymous function's must have 2 arguments: function arguments and a block");
-                        }
+                    let head = self.visit(list[0].clone())?;
 
-                        if let Expression::BLOCK(block, _) = list[1].clone() {
-                            return Value::FUNCTION(arguments, Box::new(Value::BLOCK(block)));
-                        } else {
-                            self.error("Anonymous function's second argument must be a block.");
-                        }
+                    if self.signal.is_some() {
+                        return Ok(Value::NULL);
                     }
 
-                    let expressions = list.clone();
-                    let mut values: Vec<Value> = Vec::new();
+                    if let Value::FUNCTION_ARGUMENTS(arguments) = head {
+                        let block = if let Some(Expression::BLOCK(block, _)) = list.get(1) { Some(block.clone()) } else { None };
+                        return self.anonymous_function(arguments, list.len(), block);
+                    }
 
-                    for expression in expressions {
-                        values.push(self.visit(expression));
+                    let mut values: Vec<Value> = vec![head];
+
+                    for expression in list[1..].iter().cloned() {
+                        values.push(self.visit(expression)?);
+
+                        if self.signal.is_some() {
+                            return Ok(Value::NULL);
+                        }
                     }
 
-                    Value::LIST(values)
+                    Ok(Value::LIST(values))
                 }
             }
         } else {
-            Value::NULL
+            Ok(Value::NULL)
+        }
+    }
+
+    /// Returns indices of arguments passed by name (name: value).
+    pub(crate) fn named_arguments(arguments: &[Expression]) -> Vec<usize> {
+        arguments.iter()
+            .enumerate()
+            .filter(|(_, argument)| matches!(argument, Expression::KEY_VALUE(_, _, _)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Calls a method on an object (first argument). Methods of foreign objects are looked up by their property getter first.
+    pub(crate) fn call_method(&mut self, name: &String, arguments: Vec<Value>, named: &[usize]) -> Result<Value, HierError> {
+        if let Value::FOREIGN(_, _) = &arguments[0] {
+            let method = self.get_foreign_property(&arguments[0], name)?;
+
+            if let Value::FUNCTION(_, _, _) | Value::NATIVE_FUNCTION(_, _) = method {
+                return self.call_function_value_with_named(name, method, arguments, named);
+            }
+        }
+
+        self.call_function_with_named(name, arguments, named)
+    }
+
+    /// Creates an anonymous function from function arguments heading a list of given length, with block as its second element.
+    pub(crate) fn anonymous_function(&mut self, arguments: Vec<Parameter>, length: usize, block: Option<Rc<Block>>) -> Result<Value, HierError> {
+        if length != 2 {
+            return Err(self.error("Anonymous function's must have 2 arguments: function arguments and a block"));
+        }
+
+        if let Some(block) = block {
+            Ok(Value::FUNCTION(arguments, Box::new(Value::BLOCK(block)), self.closure()))
+        } else {
+            Err(self.error("Anonymous function's second argument must be a block."))
         }
     }
 
-    pub fn visit_identifier(&mut self, value: Expression) -> Value {
+    pub fn visit_identifier(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
-        if let Expression::IDENTIFIER(value, _) = value {
+        if let Expression::IDENTIFIER(value, _, depth) = value {
             match &value as &str {
-                "true" => Value::BOOL(true),
-                "false" => Value::BOOL(false),
-                "null" => Value::NULL,
+                "true" => Ok(Value::BOOL(true)),
+                "false" => Ok(Value::BOOL(false)),
+                "null" => Ok(Value::NULL),
                 _ => {
                     if let Some(a_type) = Type::get_for_name(&value) {
-                        Value::TYPE(a_type)
+                        Ok(Value::TYPE(a_type))
+                    } else if let Some(value) = depth.and_then(|depth| self.get_at(depth, &value)) {
+                        Ok(value)
                     } else {
                         self.get(value)
                     }
                 }
             }
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_value(&mut self, value: Expression) -> Value {
+    pub fn visit_value(&mut self, value: Expression) -> Result<Value, HierError> {
         if let Expression::VALUE(value) = value {
-            value
+            Ok(value)
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_key_value(&mut self, value: Expression) -> Value {
+    pub fn visit_key_value(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
         if let Expression::KEY_VALUE(identifier, expression, _) = value {
-            Value::KEY_VALUE(identifier, Box::new(self.visit(*expression)))
+            Ok(Value::KEY_VALUE(Box::new(Value::STRING(identifier)), Box::new(self.visit(*expression)?)))
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_string(&mut self, value: Expression) -> Value {
+    pub fn visit_string(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
         if let Expression::STRING(value, _) = value {
-            Value::STRING(value.resolve(self))
+            Ok(Value::STRING(value.resolve(self)?))
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_number(&mut self, value: Expression) -> Value {
+    pub fn visit_number(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
         if let Expression::NUMBER(value, _) = value {
-            Value::NUMBER(value)
+            Ok(Value::NUMBER(value))
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 
-    pub fn visit_block(&mut self, value: Expression) -> Value {
+    pub fn visit_block(&mut self, value: Expression) -> Result<Value, HierError> {
         self.current_interpreting_location = value.get_location().clone();
 
         if let Expression::BLOCK(value, _) = value {
-            Value::BLOCK(value)
+            Ok(Value::BLOCK(value))
         } else {
-            Value::NULL
+            Ok(Value::NULL)
         }
     }
 }
\ No newline at end of file
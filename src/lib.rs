@@ -1,20 +1,41 @@
-use crate::location::Location;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::output::Sink;
 
 pub mod hier;
 pub mod value;
+pub mod table;
 pub mod types;
 pub mod environment;
 pub mod tokenizer;
 pub mod parser;
+pub mod resolver;
 pub mod interpreter;
+pub mod bytecode;
+pub mod compiler;
+pub mod vm;
+pub mod signal;
+pub mod parameter;
 pub mod native_functions;
 pub mod token;
 pub mod location;
 pub mod expression;
+pub mod error;
+pub mod diagnostic;
+pub mod output;
+pub mod input;
+pub mod conversion;
+#[cfg(feature = "serde")]
+pub mod serialization;
 mod interpolated_string;
 
-pub use crate::interpreter::warning;
+fn report(output: &Sink, diagnostic: &Diagnostic) {
+    let location = &diagnostic.span.start;
 
-fn report(error: &str, location: Location) {
-    eprintln!("({}:{} in {}) !: {}", location.line_number, location.offset, location.module, error);
+    let symbol = match diagnostic.severity {
+        Severity::ERROR => "!",
+        Severity::WARNING => "?"
+    };
+
+    // Reporting is best-effort, so failing to write is ignored.
+    let _ = writeln!(output.borrow_mut(), "({}:{} in {}) {}: {}", location.line_number, location.offset, location.module, symbol, diagnostic.message);
 }
\ No newline at end of file
@@ -21,4 +21,28 @@ impl Location {
             offset: 0
         }
     }
+}
+
+/// Part of the code between two locations (both inclusive).
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self {
+            start,
+            end
+        }
+    }
+
+    /// Span that begins and ends at the same location.
+    pub fn at(location: Location) -> Self {
+        Self {
+            start: location.clone(),
+            end: location
+        }
+    }
 }
\ No newline at end of file
@@ -1,16 +1,25 @@
-use std::collections::HashMap;
-use std::io;
-use std::io::Write;
+use std::cmp::Ordering;
+use std::rc::Rc;
+use crate::diagnostic::Diagnostic;
 use crate::environment::Environment;
+use crate::error::{ErrorKind, HierError};
+use crate::expression::Block;
 use crate::hier::Hier;
 use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::signal::Signal;
+use crate::table::Table;
 use crate::value::Value;
 use crate::tokenizer::Tokenizer;
 
 impl Environment {
-    pub fn call_addition(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_addition(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         let mut arguments = arguments;
 
+        if arguments.is_empty() {
+            return Err(self.error("Addition requires at least 1 argument."));
+        }
+
         let mut result_number = 0f64;
         let mut result_string = String::new();
 
@@ -24,7 +33,7 @@ impl Environment {
             is_number = false;
             result_string = string;
         } else {
-            self.error(&format!("Argument must be a number or string in addition. Found {}.", first.text_representation()));
+            return Err(self.error(&format!("Argument must be a number or string in addition. Found {}.", first.text_representation())));
         }
 
         for argument in arguments {
@@ -32,574 +41,894 @@ impl Environment {
                 if is_number {
                     result_number += number;
                 } else {
-                    self.error(&format!("Argument must be a string, but {} of type {} was found.", argument.text_representation(), argument.get_type().text_representation()))
+                    return Err(self.error(&format!("Argument must be a string, but {} of type {} was found.", argument.text_representation(), argument.get_type().text_representation())));
                 }
             } else if let Value::STRING(string) = argument.clone() {
                 if !is_number {
                     result_string += &string;
                 } else {
-                    self.error(&format!("Argument must be a number, but {} of type {} was found.", argument.text_representation(), argument.get_type().text_representation()))
+                    return Err(self.error(&format!("Argument must be a number, but {} of type {} was found.", argument.text_representation(), argument.get_type().text_representation())));
                 }
             } else {
-                self.error(&format!("Argument must be a number or string in addition. Found {}.", argument.text_representation()));
+                return Err(self.error(&format!("Argument must be a number or string in addition. Found {}.", argument.text_representation())));
             }
         }
 
-        if is_number { Value::NUMBER(result_number) } else { Value::STRING(result_string) }
+        if is_number { Ok(Value::NUMBER(result_number)) } else { Ok(Value::STRING(result_string)) }
     }
 
-    pub fn call_subtraction(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_subtraction(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         let mut arguments = arguments;
+
+        if arguments.is_empty() {
+            return Err(self.error("Subtraction requires at least 1 argument."));
+        }
+
         let mut result = if let Value::NUMBER(number) = arguments.remove(0) {
             number
         } else {
-            self.error("Argument must be a number in subtraction.")
+            return Err(self.error("Argument must be a number in subtraction."))
         };
 
         for argument in arguments {
             if let Value::NUMBER(number) = argument {
                 result -= number;
             } else {
-                self.error(&format!("Argument must be a number in subtraction. Found {}.", argument.text_representation()));
+                return Err(self.error(&format!("Argument must be a number in subtraction. Found {}.", argument.text_representation())));
             }
         }
 
-        Value::NUMBER(result)
+        Ok(Value::NUMBER(result))
     }
 
-    pub fn call_multiplication(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_multiplication(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         let mut arguments = arguments;
+
+        if arguments.is_empty() {
+            return Err(self.error("Multiplication requires at least 1 argument."));
+        }
+
         let mut result = if let Value::NUMBER(number) = arguments.remove(0) {
             number
         } else {
-            self.error("Argument must be a number in multiplication.")
+            return Err(self.error("Argument must be a number in multiplication."))
         };
 
         for argument in arguments {
             if let Value::NUMBER(number) = argument {
                 result *= number;
             } else {
-                self.error(&format!("Argument must be a number in multiplication. Found {}.", argument.text_representation()));
+                return Err(self.error(&format!("Argument must be a number in multiplication. Found {}.", argument.text_representation())));
             }
         }
 
-        Value::NUMBER(result)
+        Ok(Value::NUMBER(result))
     }
 
-    pub fn call_division(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_division(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         let mut arguments = arguments;
+
+        if arguments.is_empty() {
+            return Err(self.error("Division requires at least 1 argument."));
+        }
+
         let mut result = if let Value::NUMBER(number) = arguments.remove(0) {
             number
         } else {
-            self.error("Argument must be a number in division.")
+            return Err(self.error("Argument must be a number in division."))
         };
 
         for argument in arguments {
             if let Value::NUMBER(number) = argument {
                 if number == 0.0 {
-                    self.error("Dividing by 0 is forbidden.");
+                    return Err(self.error("Dividing by 0 is forbidden."));
                 }
 
                 result /= number;
             } else {
-                self.error(&format!("Argument must be a number in division. Found {}.", argument.text_representation()));
+                return Err(self.error(&format!("Argument must be a number in division. Found {}.", argument.text_representation())));
             }
         }
 
-        Value::NUMBER(result)
+        Ok(Value::NUMBER(result))
     }
 
-    pub fn call_null_coalescing(&self, arguments: Vec<Value>) -> Value {
+    pub fn call_null_coalescing(&self, arguments: Vec<Value>) -> Result<Value, HierError> {
         for argument in arguments {
             if let Value::NULL = argument {
                 continue
             } else {
-                return argument
+                return Ok(argument)
             }
         }
 
-        Value::NULL
+        Ok(Value::NULL)
     }
 
-    pub fn call_modulo(&self, arguments: Vec<Value>) -> Value {
+    pub fn call_modulo(&self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 {
-            self.error("Modulo requires only 2 operands");
+            return Err(self.error("Modulo requires only 2 operands"));
         }
 
         if let Value::NUMBER(number1) = arguments[0].clone() {
             if let Value::NUMBER(number2) = arguments[1].clone() {
-                Value::NUMBER(((number1 as i64) % (number2 as i64)) as f64)
+                if number2 as i64 == 0 {
+                    return Err(self.error("Modulo by 0 is forbidden."));
+                }
+
+                Ok(Value::NUMBER((number1 as i64).wrapping_rem(number2 as i64) as f64))
             } else {
-                self.error("Modulo requires 2 number arguments.");
+                Err(self.error("Modulo requires 2 number arguments."))
             }
         } else {
-            self.error("Modulo requires 2 number arguments.");
+            Err(self.error("Modulo requires 2 number arguments."))
         }
     }
 
-    pub fn call_is(&self, arguments: Vec<Value>) -> Value {
+    pub fn call_is(&self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 {
-            self.error("Is requires only 2 operands");
+            return Err(self.error("Is requires only 2 operands"));
         }
 
         if let Value::TYPE(a_type) = arguments[1].clone() {
-            Value::BOOL(arguments[0].get_type() == a_type)
+            Ok(Value::BOOL(arguments[0].get_type() == a_type))
         } else {
-            self.error("Is operation requires second argument to be a value type.")
+            Err(self.error("Is operation requires second argument to be a value type."))
         }
     }
 
-    pub fn call_comparison(&mut self, operation: &String, arguments: Vec<Value>) -> Value {
+    pub fn call_comparison(&mut self, operation: &str, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 {
-            self.error("Binary operations require only 2 operands");
+            return Err(self.error("Binary operations require only 2 operands"));
         }
 
-        match &operation as &str {
+        match operation {
             "==" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if argument.clone() != arguments[i - 1] {
-                        return Value::BOOL(false)
+                for (i, argument) in arguments.iter().enumerate().skip(1) {
+                    if *argument != arguments[i - 1] {
+                        return Ok(Value::BOOL(false))
                     }
                 }
 
-                return Value::BOOL(true)
+                Ok(Value::BOOL(true))
             },
             "!=" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if argument.clone() == arguments[i - 1] {
-                        return Value::BOOL(false)
+                for (i, argument) in arguments.iter().enumerate().skip(1) {
+                    if *argument == arguments[i - 1] {
+                        return Ok(Value::BOOL(false))
                     }
                 }
 
-                return Value::BOOL(true)
+                Ok(Value::BOOL(true))
             },
-            "<" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if let Value::NUMBER(number2) = argument {
-                        if let Value::NUMBER(number1) = arguments[i - 1] {
-                            if number1 >= number2.clone() {
-                                return Value::BOOL(false)
-                            }
-                        } else {
-                            self.error("< comparison operands must be numbers.")
-                        }
-                    } else {
-                        self.error("< comparison operands must be numbers.")
+            "<" | ">" | "<=" | ">=" => {
+                for (i, argument) in arguments.iter().enumerate().skip(1) {
+                    let Some(ordering) = Self::compare(&arguments[i - 1], argument) else {
+                        return Err(self.error(&format!("{} comparison operands must be numbers, strings, booleans or lists of them, of the same type.", operation)));
+                    };
+
+                    let holds = match operation {
+                        "<" => ordering == Ordering::Less,
+                        ">" => ordering == Ordering::Greater,
+                        "<=" => ordering != Ordering::Greater,
+                        _ => ordering != Ordering::Less
+                    };
+
+                    if !holds {
+                        return Ok(Value::BOOL(false))
                     }
                 }
 
-                return Value::BOOL(true)
+                Ok(Value::BOOL(true))
             },
-            ">" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if let Value::NUMBER(number2) = argument {
-                        if let Value::NUMBER(number1) = arguments[i - 1] {
-                            if number1 <= number2.clone() {
-                                return Value::BOOL(false)
-                            }
-                        } else {
-                            self.error("> comparison operands must be numbers.")
-                        }
-                    } else {
-                        self.error("> comparison operands must be numbers.")
-                    }
-                }
+            _ => Ok(Value::NULL) // We never reach this place, because call_function checks whether the operation is a valid one for this function.
+        }
+    }
 
-                return Value::BOOL(true)
-            },
-            "<=" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if let Value::NUMBER(number2) = argument {
-                        if let Value::NUMBER(number1) = arguments[i - 1] {
-                            if number1 > number2.clone() {
-                                return Value::BOOL(false)
-                            }
-                        } else {
-                            self.error("<= comparison operands must be numbers.")
-                        }
-                    } else {
-                        self.error("<= comparison operands must be numbers.")
+    /// Orders values of the same type: numbers, strings (lexicographically), booleans (false first) and lists (by their elements,
+    /// then by length). Returns None if the values aren't comparable.
+    pub(crate) fn compare(first: &Value, second: &Value) -> Option<Ordering> {
+        match (first, second) {
+            // Equal numbers (0 and -0, all NaNs) are ordered as such, so ordering is consistent with equality.
+            (Value::NUMBER(_), Value::NUMBER(_)) if first == second => Some(Ordering::Equal),
+            (Value::NUMBER(number1), Value::NUMBER(number2)) => Some(number1.total_cmp(number2)),
+            (Value::STRING(string1), Value::STRING(string2)) => Some(string1.cmp(string2)),
+            (Value::BOOL(bool1), Value::BOOL(bool2)) => Some(bool1.cmp(bool2)),
+            (Value::LIST(list1), Value::LIST(list2)) => {
+                for (element1, element2) in list1.iter().zip(list2) {
+                    match Self::compare(element1, element2)? {
+                        Ordering::Equal => continue,
+                        ordering => return Some(ordering)
                     }
                 }
 
-                return Value::BOOL(true)
+                Some(list1.len().cmp(&list2.len()))
             },
-            ">=" => {
-                for (i, argument) in arguments.iter().enumerate() {
-                    if let Value::NUMBER(number2) = argument {
-                        if let Value::NUMBER(number1) = arguments[i - 1] {
-                            if number1 < number2.clone() {
-                                return Value::BOOL(false)
-                            }
-                        } else {
-                            self.error(">= comparison operands must be numbers.")
-                        }
-                    } else {
-                        self.error(">= comparison operands must be numbers.")
-                    }
-                }
-
-                return Value::BOOL(true)
-            }
-            _ => Value::NULL // We never reach this place, because call_function checks whether the operation is a valid one for this function.
+            _ => None
         }
     }
 
-    pub fn call_logical(&mut self, operation: &String, arguments: Vec<Value>) -> Value {
-        if arguments.len() == 0 {
-            return Value::BOOL(true);
+    pub fn call_logical(&mut self, operation: &str, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.is_empty() {
+            return Ok(Value::BOOL(true));
         }
 
         for argument in arguments {
             if let Value::BOOL(value) = argument {
                 if operation == "&&" {
                     if !value {
-                        return Value::BOOL(false);
+                        return Ok(Value::BOOL(false));
                     }
                 } else { // ||
                     if value {
-                        return Value::BOOL(true);
+                        return Ok(Value::BOOL(true));
                     }
                 };
             } else {
-                self.error("Operands of logical operations must be booleans or boolean expressions.")
+                return Err(self.error("Operands of logical operations must be booleans or boolean expressions."));
             }
         }
 
         if operation == "&&" {
-            Value::BOOL(true)
+            Ok(Value::BOOL(true))
         } else { // ||
-            Value::BOOL(false)
+            Ok(Value::BOOL(false))
         }
     }
 
-    pub fn call_if(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_if(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 && arguments.len() != 3 {
-            self.error("If must have only 2 or 3 arguments: condition and block (optionally else block).");
+            return Err(self.error("If must have only 2 or 3 arguments: condition and block (optionally else block)."));
         }
 
         let condition = if let Value::BOOL(condition) = arguments[0] {
             condition
         } else {
-            self.error("If's condition must evaluate to a boolean.");
+            return Err(self.error("If's condition must evaluate to a boolean."));
         };
 
-        self.begin_scope();
-
-        if condition {
-            return if let Value::BLOCK(block) = arguments[1].clone() {
-                let value = self.interpret_block(block);
-                value
-            } else {
-                arguments[1].clone()
-            }
+        let branch = if condition {
+            arguments[1].clone()
         } else if arguments.len() == 3 {
-            return if let Value::BLOCK(block) = arguments[2].clone() {
-                let value = self.interpret_block(block);
-                value
-            } else {
-                arguments[2].clone()
-            }
-        }
-
-        self.end_scope();
+            arguments[2].clone()
+        } else {
+            return Ok(Value::NULL);
+        };
 
-        Value::NULL
+        if let Value::BLOCK(block) = branch {
+            self.begin_scope();
+            let result = self.interpret_block(block);
+            self.end_scope()?;
+            result
+        } else {
+            Ok(branch)
+        }
     }
 
-    pub fn call_while(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_while(&mut self, mut arguments: Vec<Value>) -> Result<Value, HierError> {
+        let label = self.take_label(&mut arguments)?;
+
         if arguments.len() != 2 {
-            self.error("While must have 2 arguments: a condition block and an execution block.");
+            return Err(self.error("While must have 2 arguments: a condition block and an execution block."));
         }
 
         self.begin_scope();
 
         if let Value::BLOCK(_) = arguments[0] { } else {
-            self.error("While's first argument must be a block.");
+            return Err(self.error("While's first argument must be a block."));
         }
 
+        let mut result = Value::NULL;
+
         if let Value::BLOCK(block) = arguments[1].clone() {
             loop {
                 let condition = if let Value::BLOCK(condition_block) = arguments[0].clone() {
-                    if let Value::BOOL(condition) = self.interpret_block(condition_block) {
+                    let condition = self.interpret_block(condition_block)?;
+
+                    if self.signal.is_some() {
+                        break;
+                    }
+
+                    if let Value::BOOL(condition) = condition {
                         condition
                     } else {
-                        self.error("While's condition must return a boolean (boolean must be the last expression's result).");
+                        return Err(self.error("While's condition must return a boolean (boolean must be the last expression's result)."));
                     }
                 } else {
-                    self.error("While's condition must be a condition block returning a boolean (boolean must be the last expression's result).");
+                    return Err(self.error("While's condition must be a condition block returning a boolean (boolean must be the last expression's result)."));
                 };
 
-                if !condition {
+                if !condition || !self.run_iteration(&block, None, &label, &mut result)? {
                     break;
                 }
-
-                self.begin_scope();
-                if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                    if error_message == "LoopExit".to_string() {
-                        self.end_scope();
-                        self.end_scope();
-                        break;
-                    }
-                }
-                self.end_scope();
             }
         }
 
-        self.end_scope();
+        self.end_scope()?;
 
-        Value::NULL
+        Ok(result)
     }
 
-    pub fn call_try(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_try(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 {
-            self.error("Try must have 2 arguments: a value and execution block.");
+            return Err(self.error("Try must have 2 arguments: a value and execution block."));
         }
 
         self.begin_scope();
 
         let result = if let Value::ERROR(error_message) = arguments[0].clone() {
             if let Value::BLOCK(block) = arguments[1].clone() {
-                self.declare("error".to_string(), Value::STRING(error_message));
-                let value = self.interpret_block(block.clone());
-                self.end_scope();
-                value
+                self.declare("error".to_string(), Value::STRING(error_message))?;
+                self.interpret_block(block.clone())?
             } else {
-                self.error("Try's second argument must be a block.");
+                return Err(self.error("Try's second argument must be a block."));
             }
         } else {
             arguments[0].clone()
         };
 
-        self.end_scope();
+        self.end_scope()?;
 
-        result
+        Ok(result)
     }
 
-    pub fn call_for(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_for(&mut self, mut arguments: Vec<Value>) -> Result<Value, HierError> {
+        let label = self.take_label(&mut arguments)?;
+
         if arguments.len() != 2 {
-            self.error("For must have 2 arguments: a list or a string and execution block.");
+            return Err(self.error("For must have 2 arguments: a list or a string and execution block."));
         }
 
+        let block = if let Value::BLOCK(block) = arguments[1].clone() {
+            block
+        } else {
+            return Err(self.error("For's second argument must be a block."));
+        };
+
+        let elements: Vec<Value> = match arguments[0].clone() {
+            Value::LIST(list) => list,
+            Value::STRING(string) => string.chars().map(|element| Value::STRING(element.to_string())).collect(),
+            Value::TABLE(table) => table.into_iter().map(|(key, value)| Value::KEY_VALUE(Box::new(key), Box::new(value))).collect(),
+            _ => return Err(self.error("For's first argument must be a list."))
+        };
+
         self.begin_scope();
 
-        if let Value::LIST(list) = arguments[0].clone() {
-            if let Value::BLOCK(block) = arguments[1].clone() {
-                for element in list {
-                    self.begin_scope();
-                    self.declare("element".to_string(), element);
-                    if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                        if error_message == "LoopExit".to_string() {
-                            self.end_scope();
-                            self.end_scope();
-                            break;
-                        }
-                    }
-                    self.end_scope();
-                }
-            } else {
-                self.error("For's second argument must be a block.");
-            }
-        } else if let Value::STRING(string) = arguments[0].clone() {
-            if let Value::BLOCK(block) = arguments[1].clone() {
-                for element in string.chars() {
-                    self.begin_scope();
-                    self.declare("element".to_string(), Value::STRING(element.to_string()));
-                    if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                        if error_message == "LoopExit".to_string() {
-                            self.end_scope();
-                            self.end_scope();
-                            break;
-                        }
-                    }
-                    self.end_scope();
-                }
-            } else {
-                self.error("For's second argument must be a block.");
-            }
-        } else if let Value::TABLE(table) = arguments[0].clone() {
-            if let Value::BLOCK(block) = arguments[1].clone() {
-                for (key, value) in table.iter() {
-                    self.begin_scope();
-                    self.declare("element".to_string(), Value::KEY_VALUE(key.to_string(), Box::new(value.clone())));
-                    if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                        if error_message == "LoopExit".to_string() {
-                            self.end_scope();
-                            self.end_scope();
-                            break;
-                        }
-                    }
-                    self.end_scope();
-                }
-            } else {
-                self.error("For's second argument must be a block.");
+        let mut result = Value::NULL;
+
+        for element in elements {
+            if !self.run_iteration(&block, Some(element), &label, &mut result)? {
+                break;
             }
-        } else {
-            self.error("For's first argument must be a list.");
-        };
+        }
 
-        self.end_scope();
+        self.end_scope()?;
 
-        Value::NULL
+        Ok(result)
     }
 
-    pub fn call_repeat(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_repeat(&mut self, mut arguments: Vec<Value>) -> Result<Value, HierError> {
+        let label = self.take_label(&mut arguments)?;
+
         if arguments.len() != 2 && arguments.len() != 1 {
-            self.error("Repeat must have only 2 arguments: a number (optional) and execution block.");
+            return Err(self.error("Repeat must have only 2 arguments: a number (optional) and execution block."));
         }
 
-         self.begin_scope();
+        self.begin_scope();
+
+        let mut result = Value::NULL;
 
         if arguments.len() == 2 {
             let repetitions = if let Value::NUMBER(number) = arguments[0].clone() {
                 if number < 1f64 {
-                    self.error("Repeat's first argument must be a number greater than 0.");
+                    return Err(self.error("Repeat's first argument must be a number greater than 0."));
                 }
                 number as i64
             } else {
-                self.error("Repeat's first argument must be a number.");
+                return Err(self.error("Repeat's first argument must be a number."));
             };
 
             if let Value::BLOCK(block) = arguments[1].clone() {
                 for _ in 0..repetitions {
-                    self.begin_scope();
-                    if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                        if error_message == "LoopExit".to_string() {
-                            self.end_scope();
-                            self.end_scope();
-                            break;
-                        }
+                    if !self.run_iteration(&block, None, &label, &mut result)? {
+                        break;
                     }
-                    self.end_scope();
                 }
             }
-        } else {
-            loop {
-                if let Value::BLOCK(block) = arguments[0].clone() {
-                    self.begin_scope();
-                    if let Value::ERROR(error_message) = self.interpret_block(block.clone()) {
-                        if error_message == "LoopExit".to_string() {
-                            self.end_scope();
-                            self.end_scope();
-                            break;
-                        }
-                    }
-                    self.end_scope();
+        } else if let Value::BLOCK(block) = arguments[0].clone() {
+            while self.run_iteration(&block, None, &label, &mut result)? {}
+        }
+
+        self.end_scope()?;
+
+        Ok(result)
+    }
+
+    /// Runs an iteration of a loop (with given label) in a new scope, with element declared in it (if given), and stores its
+    /// value (or value of break) as the result of the loop. Returns false if the loop must stop, because the iteration broke out of
+    /// it or a signal for an outer construct (like return) is being propagated.
+    fn run_iteration(&mut self, block: &Rc<Block>, element: Option<Value>, label: &Option<String>, result: &mut Value) -> Result<bool, HierError> {
+        self.begin_scope();
+
+        if let Some(element) = element {
+            self.declare("element".to_string(), element)?;
+        }
+
+        *result = self.interpret_block(block.clone())?;
+        self.end_scope()?;
+
+        match &self.signal {
+            Some(Signal::BREAK(target, _)) if target.is_none() || target == label => {
+                if let Some(Signal::BREAK(_, value)) = self.signal.take() {
+                    *result = value;
                 }
-            }
+
+                Ok(false)
+            },
+            Some(Signal::CONTINUE(target)) if target.is_none() || target == label => {
+                self.signal = None;
+                Ok(true)
+            },
+            Some(_) => Ok(false),
+            None => Ok(true)
         }
+    }
+
+    /// Takes label of a loop (label: "name") from the first argument, if there is one.
+    fn take_label(&self, arguments: &mut Vec<Value>) -> Result<Option<String>, HierError> {
+        if let Some(Value::KEY_VALUE(key, value)) = arguments.first() {
+            if matches!(&**key, Value::STRING(key) if key == "label") {
+                let Value::STRING(label) = &**value else {
+                    return Err(self.error("Label must be a string."));
+                };
+
+                let label = label.clone();
+                arguments.remove(0);
 
-        self.end_scope();
+                return Ok(Some(label));
+            }
+        }
 
-        Value::NULL
+        Ok(None)
     }
 
-    pub fn call_run(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_run(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         let mut last_result = Value::NULL;
 
         for argument in arguments {
             if let Value::BLOCK(block) = argument {
-                last_result = self.interpret_block(block);
+                last_result = self.interpret_block(block)?;
+
+                if self.signal.is_some() {
+                    break;
+                }
             } else {
                 last_result = argument;
             }
         }
 
-        last_result
+        Ok(last_result)
     }
 
-    pub fn call_map(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_map(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 {
-            self.error("Map function requires 2 arguments: a object and a block.");
+            return Err(self.error("Map function requires 2 arguments: a object and a block or function."));
         }
 
-        let object = &arguments[0];
-        let block = if let Value::BLOCK(block) = &arguments[1] {
-            block
-        } else {
-            self.error("Map functions 2nd argument must be a block.");
-        };
+        let object = arguments[0].clone();
+        let callback = self.callback("Map", &arguments[1])?;
 
         match object {
             Value::LIST(list) => {
                 let mut new_list: Vec<Value> = Vec::new();
 
                 for element in list {
-                    self.begin_scope();
-                    self.declare("element".to_string(), element.clone());
-                    new_list.push(self.interpret_block(block.clone()));
-                    self.end_scope();
+                    new_list.push(self.call_callback(&callback, &["element"], vec![element])?);
+
+                    if self.signal.is_some() {
+                        break;
+                    }
                 }
 
-                Value::LIST(new_list)
+                Ok(Value::LIST(new_list))
             },
-            _ => {
-                self.begin_scope();
-                self.declare("element".to_string(), object.clone());
-                let result = self.interpret_block(block.clone());
-                self.end_scope();
-                result
+            _ => self.call_callback(&callback, &["element"], vec![object])
+        }
+    }
+
+    /// Checks that the argument of an operation is a block or a function, which can be called by call_callback.
+    fn callback(&self, operation: &str, argument: &Value) -> Result<Value, HierError> {
+        match argument {
+            Value::BLOCK(_) | Value::FUNCTION(_, _, _) | Value::NATIVE_FUNCTION(_, _) => Ok(argument.clone()),
+            _ => Err(self.error(&format!("{} operation requires last argument to be a block or a function.", operation)))
+        }
+    }
+
+    /// Calls a block or a function passed to an operation. Functions receive the arguments, blocks have them declared as
+    /// variables with given names (like element).
+    fn call_callback(&mut self, callback: &Value, names: &[&str], arguments: Vec<Value>) -> Result<Value, HierError> {
+        if let Value::BLOCK(block) = callback {
+            self.begin_scope();
+
+            for (name, argument) in names.iter().zip(arguments) {
+                self.declare(name.to_string(), argument)?;
             }
+
+            let result = self.interpret_block(block.clone())?;
+            self.end_scope()?;
+            Ok(result)
+        } else {
+            self.call_function_value(&callback.text_representation(), callback.clone(), arguments)
         }
     }
 
-    pub fn call_print(&mut self, arguments: Vec<Value>) -> Value {
-        for argument in arguments {
-            print!("{}", argument.text_representation());
+    /// Calls a callback of an operation with an element, which must return a boolean. Returns None if a signal (like break) is
+    /// being propagated.
+    fn call_predicate(&mut self, operation: &str, callback: &Value, element: Value) -> Result<Option<bool>, HierError> {
+        let result = self.call_callback(callback, &["element"], vec![element])?;
+
+        if self.signal.is_some() {
+            return Ok(None);
+        }
+
+        match result {
+            Value::BOOL(result) => Ok(Some(result)),
+            _ => Err(self.error(&format!("{}'s block or function must return a boolean.", operation)))
+        }
+    }
+
+    /// Returns the list and callback of an operation called with them (like filter).
+    fn list_and_callback(&self, operation: &str, arguments: Vec<Value>) -> Result<(Vec<Value>, Value), HierError> {
+        match <[Value; 2]>::try_from(arguments) {
+            Ok([Value::LIST(list), callback]) => Ok((list, self.callback(operation, &callback)?)),
+            _ => Err(self.error(&format!("{} operation requires 2 arguments: a list and a block or function.", operation)))
+        }
+    }
+
+    pub fn call_filter(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let (list, callback) = self.list_and_callback("Filter", arguments)?;
+        let mut filtered: Vec<Value> = Vec::new();
+
+        for element in list {
+            match self.call_predicate("Filter", &callback, element.clone())? {
+                Some(true) => filtered.push(element),
+                Some(false) => {},
+                None => return Ok(Value::NULL)
+            }
+        }
+
+        Ok(Value::LIST(filtered))
+    }
+
+    pub fn call_find(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let (list, callback) = self.list_and_callback("Find", arguments)?;
+
+        for element in list {
+            match self.call_predicate("Find", &callback, element.clone())? {
+                Some(true) => return Ok(element),
+                Some(false) => {},
+                None => return Ok(Value::NULL)
+            }
+        }
+
+        Ok(Value::NULL)
+    }
+
+    /// Checks if any (or all, if all is true) elements satisfy the predicate.
+    pub fn call_any(&mut self, all: bool, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let operation = if all { "All" } else { "Any" };
+        let (list, callback) = self.list_and_callback(operation, arguments)?;
+
+        for element in list {
+            match self.call_predicate(operation, &callback, element)? {
+                Some(result) if result != all => return Ok(Value::BOOL(result)),
+                Some(_) => {},
+                None => return Ok(Value::NULL)
+            }
+        }
+
+        Ok(Value::BOOL(all))
+    }
+
+    /// Folds the list using a callback receiving the accumulator and an element. Without the initial value, the first element
+    /// is used.
+    pub fn call_reduce(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 2 && arguments.len() != 3 {
+            return Err(self.error("Reduce operation requires 2 or 3 arguments: a list, initial value (optional, if none, the first element is used) and a block or function."));
+        }
+
+        let mut arguments = arguments;
+        let callback = arguments.pop().unwrap_or(Value::NULL);
+        let callback = self.callback("Reduce", &callback)?;
+
+        let Value::LIST(list) = arguments.remove(0) else {
+            return Err(self.error("Reduce operation requires first argument to be a list."));
+        };
+
+        let mut list = list.into_iter();
+
+        let Some(mut accumulator) = arguments.pop().or_else(|| list.next()) else {
+            return Ok(Value::NULL);
+        };
+
+        for element in list {
+            accumulator = self.call_callback(&callback, &["accumulator", "element"], vec![accumulator, element])?;
+
+            if self.signal.is_some() {
+                return Ok(Value::NULL);
+            }
+        }
+
+        Ok(accumulator)
+    }
+
+    /// Sorts the list in natural order (see compare) or using a comparator, which receives two elements (first and second) and
+    /// returns a negative number if first goes before second, a positive one if it goes after it and 0 if they are equal.
+    pub fn call_sort(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 1 && arguments.len() != 2 {
+            return Err(self.error("Sort operation requires 1 or 2 arguments: a list and a block or function comparing elements (optional)."));
+        }
+
+        let mut arguments = arguments.into_iter();
+
+        let Some(Value::LIST(list)) = arguments.next() else {
+            return Err(self.error("Sort operation requires first argument to be a list."));
+        };
+
+        let sorted = match arguments.next() {
+            Some(comparator) => {
+                let comparator = self.callback("Sort", &comparator)?;
+
+                self.merge_sort(list, &mut |environment: &mut Self, first: &Value, second: &Value| {
+                    let result = environment.call_callback(&comparator, &["first", "second"], vec![first.clone(), second.clone()])?;
+
+                    match result {
+                        _ if environment.signal.is_some() => Ok(Ordering::Equal),
+                        Value::NUMBER(result) => Ok(result.partial_cmp(&0f64).unwrap_or(Ordering::Equal)),
+                        _ => Err(environment.error("Sort's comparator must return a number."))
+                    }
+                })?
+            },
+            None => self.merge_sort(list, &mut |environment: &mut Self, first: &Value, second: &Value| {
+                Self::compare(first, second).ok_or_else(|| environment.error(&format!("Sort operation can't compare {} and {}.", first.text_representation(), second.text_representation())))
+            })?
+        };
+
+        if self.signal.is_some() {
+            return Ok(Value::NULL);
+        }
+
+        Ok(Value::LIST(sorted))
+    }
+
+    /// Sorts the list by keys returned by the callback for each element, in their natural order.
+    pub fn call_sort_by(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let (list, callback) = self.list_and_callback("Sort by", arguments)?;
+        let mut keyed: Vec<(Value, Value)> = Vec::new();
+
+        for element in list {
+            let key = self.call_callback(&callback, &["element"], vec![element.clone()])?;
+
+            if self.signal.is_some() {
+                return Ok(Value::NULL);
+            }
+
+            keyed.push((key, element));
+        }
+
+        let sorted = self.merge_sort(keyed, &mut |environment: &mut Self, (first, _): &(Value, Value), (second, _): &(Value, Value)| {
+            Self::compare(first, second).ok_or_else(|| environment.error(&format!("Sort by operation can't compare keys {} and {}.", first.text_representation(), second.text_representation())))
+        })?;
+
+        Ok(Value::LIST(sorted.into_iter().map(|(_, element)| element).collect()))
+    }
+
+    /// Stable merge sort, which (unlike sorting of the standard library) allows the comparison to fail and doesn't require it
+    /// to be a total order, which comparators written in Hier may not be.
+    fn merge_sort<T>(&mut self, mut list: Vec<T>, compare: &mut impl FnMut(&mut Self, &T, &T) -> Result<Ordering, HierError>) -> Result<Vec<T>, HierError> {
+        if list.len() <= 1 {
+            return Ok(list);
         }
 
-        std::io::stdout().flush().expect("Failed to flush stdout.");
+        let right = list.split_off(list.len() / 2);
+        let mut left = self.merge_sort(list, compare)?.into_iter().peekable();
+        let mut right = self.merge_sort(right, compare)?.into_iter().peekable();
+        let mut merged: Vec<T> = Vec::new();
+
+        while let (Some(first), Some(second)) = (left.peek(), right.peek()) {
+            // Elements from the left go first, unless they are greater, so equal elements keep their order.
+            let next = if compare(self, first, second)? == Ordering::Greater { right.next() } else { left.next() };
+            merged.extend(next);
+        }
 
-        Value::NULL
+        merged.extend(left);
+        merged.extend(right);
+        Ok(merged)
     }
 
-    pub fn call_println(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_zip(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let mut lists: Vec<std::vec::IntoIter<Value>> = Vec::new();
+
         for argument in arguments {
-            print!("{}", argument.text_representation());
+            if let Value::LIST(list) = argument {
+                lists.push(list.into_iter());
+            } else {
+                return Err(self.error(&format!("Zip operation's all arguments must be lists, but {} was found.", argument.text_representation())));
+            }
+        }
+
+        let mut zipped: Vec<Value> = Vec::new();
+
+        // Zipped list is as long as the shortest one.
+        while let Some(elements) = lists.iter_mut().map(|list| list.next()).collect::<Option<Vec<Value>>>() {
+            if elements.is_empty() {
+                break;
+            }
+
+            zipped.push(Value::LIST(elements));
         }
 
-        print!("\n");
+        Ok(Value::LIST(zipped))
+    }
 
-        Value::NULL
+    /// Returns elements of the list as key values with their indices as keys.
+    pub fn call_enumerate(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::LIST(list)]) => Ok(Value::LIST(list.into_iter().enumerate().map(|(i, element)| Value::KEY_VALUE(Box::new(Value::NUMBER(i as f64)), Box::new(element))).collect())),
+            _ => Err(self.error("Enumerate operation requires 1 argument that is a list."))
+        }
     }
 
-    pub fn call_list(&mut self, arguments: Vec<Value>) -> Value {
-        Value::LIST(arguments)
+    /// Flattens lists in the list by one level.
+    pub fn call_flatten(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::LIST(list)]) => Ok(Value::LIST(list.into_iter().flat_map(|element| match element {
+                Value::LIST(inner) => inner,
+                element => vec![element]
+            }).collect())),
+            _ => Err(self.error("Flatten operation requires 1 argument that is a list."))
+        }
     }
 
-    pub fn call_read(&mut self, arguments: Vec<Value>) -> Value {
-        if arguments.len() == 0 {
-            let mut line = String::new();
-            if let Err(error_message) = io::stdin().read_line(&mut line) {
-                self.error(&format!("Failed to read line: {}.", error_message));
-            };
-            if 1 < line.len() {
-                line.remove(line.len() - 1);
+    pub fn call_reverse(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::LIST(list)]) => Ok(Value::LIST(list.into_iter().rev().collect())),
+            Ok([Value::STRING(string)]) => Ok(Value::STRING(string.chars().rev().collect())),
+            _ => Err(self.error("Reverse operation requires 1 argument that is an array (list or string)."))
+        }
+    }
+
+    /// Removes repeated elements of the list, keeping the first occurrence of each.
+    pub fn call_unique(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::LIST(list)]) => {
+                let mut unique: Vec<Value> = Vec::new();
+
+                for element in list {
+                    if !unique.contains(&element) {
+                        unique.push(element);
+                    }
+                }
+
+                Ok(Value::LIST(unique))
+            },
+            _ => Err(self.error("Unique operation requires 1 argument that is a list."))
+        }
+    }
+
+    /// Returns elements of an array from start to end (exclusive, the end of array if none).
+    pub fn call_slice(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 2 && arguments.len() != 3 {
+            return Err(self.error("Slice operation requires 2 or 3 arguments: an array (list or string), start and end (optional, if none, slice to the end)."));
+        }
+
+        let length = match &arguments[0] {
+            Value::LIST(list) => list.len(),
+            Value::STRING(string) => string.chars().count(),
+            _ => return Err(self.error("Slice operation requires first argument to be an array (list or string)."))
+        };
+
+        let (start, end) = match (&arguments[1], arguments.get(2)) {
+            (Value::NUMBER(start), None) => (*start, length as f64),
+            (Value::NUMBER(start), Some(Value::NUMBER(end))) => (*start, *end),
+            _ => return Err(self.error("Slice operation requires start and end to be numbers."))
+        };
+
+        if start < 0f64 || end < start || end > length as f64 {
+            return Err(self.error(&format!("Slice {} to {} is out of bounds ({} elements).", start, end, length)));
+        }
+
+        let (start, end) = (start as usize, end as usize);
+
+        match &arguments[0] {
+            Value::LIST(list) => Ok(Value::LIST(list[start..end].to_vec())),
+            Value::STRING(string) => Ok(Value::STRING(string.chars().skip(start).take(end - start).collect())),
+            _ => Ok(Value::NULL)
+        }
+    }
+
+    pub fn call_concat(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let mut concatenated: Vec<Value> = Vec::new();
+
+        for argument in arguments {
+            if let Value::LIST(list) = argument {
+                concatenated.extend(list);
             } else {
-                line = "".to_string();
+                return Err(self.error(&format!("Concat operation's all arguments must be lists, but {} was found.", argument.text_representation())));
             }
+        }
 
-            Value::STRING(line)
+        Ok(Value::LIST(concatenated))
+    }
+
+    /// Returns index of the first element of the list equal to the value (or of the substring of the string), null if there is none.
+    pub fn call_index_of(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 2]>::try_from(arguments) {
+            Ok([Value::LIST(list), value]) => Ok(list.iter().position(|element| *element == value).map_or(Value::NULL, |i| Value::NUMBER(i as f64))),
+            Ok([Value::STRING(string), Value::STRING(substring)]) => Ok(string.find(&substring).map_or(Value::NULL, |i| Value::NUMBER(string[..i].chars().count() as f64))),
+            _ => Err(self.error("Index of operation requires 2 arguments: a list and a value, or a string and a string."))
+        }
+    }
+
+    pub fn call_contains(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        match <[Value; 2]>::try_from(arguments) {
+            Ok([Value::LIST(list), value]) => Ok(Value::BOOL(list.contains(&value))),
+            Ok([Value::STRING(string), Value::STRING(substring)]) => Ok(Value::BOOL(string.contains(&substring))),
+            _ => Err(self.error("Contains operation requires 2 arguments: a list and a value, or a string and a string."))
+        }
+    }
+
+    pub fn call_print(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        for argument in arguments {
+            self.write_output(&argument.text_representation())?;
+        }
+
+        Ok(Value::NULL)
+    }
+
+    pub fn call_println(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        for argument in arguments {
+            self.write_output(&argument.text_representation())?;
+        }
+
+        self.write_output("\n")?;
+
+        Ok(Value::NULL)
+    }
+
+    pub fn call_list(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        Ok(Value::LIST(arguments))
+    }
+
+    pub fn call_read(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.is_empty() {
+            let line = self.input.borrow_mut().read_line();
+
+            match line {
+                Ok(Some(line)) => Ok(Value::STRING(line)),
+                Ok(None) => Ok(Value::NULL),
+                Err(error_message) => Err(self.error(&format!("Failed to read line: {}.", error_message)))
+            }
         } else {
-            self.error("Read operation requires 0 arguments.");
+            Err(self.error("Read operation requires 0 arguments."))
         }
     }
 
-    pub fn call_negate(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_negate(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Negation requires 1 boolean argument.");
+            return Err(self.error("Negation requires 1 boolean argument."));
         }
 
         if let Value::BOOL(boolean) = arguments[0] {
-            Value::BOOL(!boolean)
+            Ok(Value::BOOL(!boolean))
         } else {
-            self.error("Negation requires 1 boolean argument.");
+            Err(self.error("Negation requires 1 boolean argument."))
         }
     }
 
-    pub fn call_import(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_import(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Import requires 1 string argument.");
+            return Err(self.error("Import requires 1 string argument."));
         }
 
         if let Value::STRING(path) = arguments[0].clone() {
@@ -611,8 +940,8 @@ impl Environment {
                 origin_path = std::env::current_dir().unwrap().to_str().unwrap().to_string() + "/" + &origin_path;
             }
 
-            if !origin_path.ends_with("/") {
-                let mut origin_path_split = origin_path.split("/").collect::<Vec<&str>>();
+            if !origin_path.ends_with('/') {
+                let mut origin_path_split = origin_path.split('/').collect::<Vec<&str>>();
                 origin_path_split.remove(origin_path_split.len() - 1);
                 origin_path = origin_path_split.join("/");
                 origin_path += "/"
@@ -626,7 +955,7 @@ impl Environment {
                 path = origin_path.clone() + &path;
             }
 
-            if !path.starts_with("/") {
+            if !path.starts_with('/') {
                 path = origin_path.clone() + &path;
             }
 
@@ -638,32 +967,49 @@ impl Environment {
 
             let mut tokenizer = Tokenizer::new_with_name(contents, path.clone());
 
-            if tokenizer.tokenize_module() {
-                eprintln!("Failed to import file {}.", path);
-                (self.exit_handler)()
+            tokenizer.tokenize_module();
+            let error = Diagnostic::first_error(&tokenizer.diagnostics, ErrorKind::LEX);
+            self.diagnostics.append(&mut tokenizer.diagnostics);
+
+            if let Some(error) = error {
+                return Err(error);
             }
 
-            let mut parser = Parser::new(tokenizer.tokens, self.module_reader, self.exit_handler);
+            let mut parser = Parser::with_spans(tokenizer.tokens, tokenizer.spans);
 
-            if parser.parse() {
-                println!("Failed.");
-                (self.exit_handler)();
+            parser.parse();
+            let error = Diagnostic::first_error(&parser.diagnostics, ErrorKind::PARSE);
+            self.diagnostics.append(&mut parser.diagnostics);
+
+            if let Some(error) = error {
+                return Err(error);
             }
 
-            let mut environment = Environment::new(false, path, self.module_reader, self.exit_handler);
+            let mut environment = self.new_module(path);
+            let mut resolver = Resolver::new(environment.is_in_repl());
+
+            resolver.resolve(&mut parser.code);
+            let error = Diagnostic::first_error(&resolver.diagnostics, ErrorKind::RESOLVE);
+            self.diagnostics.append(&mut resolver.diagnostics);
+
+            if let Some(error) = error {
+                return Err(error);
+            }
 
             environment.code = parser.code;
-            environment.interpret();
+            let result = environment.interpret();
+            self.diagnostics.append(&mut environment.diagnostics);
+            result?;
 
-            Value::ENVIRONMENT(Box::new(environment))
+            Ok(Value::ENVIRONMENT(Box::new(environment)))
         } else {
-            self.error("Import requires 1 string argument.");
+            Err(self.error("Import requires 1 string argument."))
         }
     }
 
-    pub fn call_number(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_number(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Number conversion requires 1 argument.");
+            return Err(self.error("Number conversion requires 1 argument."));
         }
 
         if let Value::STRING(string) = arguments[0].clone() {
@@ -676,259 +1022,362 @@ impl Environment {
             let number = string.parse::<f64>();
 
             match number {
-                Ok(number) => Value::NUMBER(number),
+                Ok(number) => Ok(Value::NUMBER(number)),
                 Err(err) => {
-                    println!("Warning: Failed to convert number {} due to an error: {}. Returning 0.", string, err);
-                    Value::NUMBER(0f64)
+                    self.warning(&format!("Failed to convert number {} due to an error: {}. Returning 0.", string, err));
+                    Ok(Value::NUMBER(0f64))
                 }
             }
         } else {
-            println!("Warning: Failed to convert to number from {}, because it is an unsupported type. Returning 0.", arguments[0].clone().get_type().text_representation());
-            Value::NULL
+            self.warning(&format!("Failed to convert to number from {}, because it is an unsupported type. Returning null.", arguments[0].clone().get_type().text_representation()));
+            Ok(Value::NULL)
         }
     }
 
-    pub fn call_table(&mut self, arguments: Vec<Value>) -> Value {
-        let mut table: HashMap<String, Value> = HashMap::new();
+    pub fn call_table(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let mut table = Table::new();
 
         for argument in arguments {
             if let Value::KEY_VALUE(key, value) = argument {
-                table.insert(key, *value);
+                table.insert(*key, *value);
             } else {
-                self.error(&format!("Table operation's all arguments must be key-values, but {} was found.", argument.text_representation()));
+                return Err(self.error(&format!("Table operation's all arguments must be key-values, but {} was found.", argument.text_representation())));
             }
         }
 
-        Value::TABLE(table)
+        Ok(Value::TABLE(table))
     }
 
-    pub fn call_string(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_string(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("String conversion requires 1 argument.");
+            return Err(self.error("String conversion requires 1 argument."));
         }
 
-        Value::STRING(arguments[0].text_representation())
+        Ok(Value::STRING(arguments[0].text_representation()))
     }
 
-    pub fn call_length(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_length(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Length operation requires 1 argument that is an array (list or string).");
+            return Err(self.error("Length operation requires 1 argument that is an array (list or string)."));
         }
 
         if let Value::LIST(list) = arguments[0].clone() {
-            Value::NUMBER(list.len() as f64)
+            Ok(Value::NUMBER(list.len() as f64))
         } else if let Value::STRING(string) = arguments[0].clone() {
-            Value::NUMBER(string.len() as f64)
+            Ok(Value::NUMBER(string.chars().count() as f64))
         } else {
-            self.error("Length operation requires 1 argument that is an array (list or string).");
+            Err(self.error("Length operation requires 1 argument that is an array (list or string)."))
         }
     }
 
-    pub fn call_remove(&mut self, arguments: Vec<Value>) -> Value {
-        if arguments.len() == 1 {
-            if let Value::LIST(list) = arguments[0].clone() {
-                let mut list = list;
-                list.remove(list.len() - 1);
-                Value::LIST(list)
-            } else if let Value::STRING(string) = arguments[0].clone() {
+    pub fn call_remove(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 1 && arguments.len() != 2 {
+            return Err(self.error("Remove operation requires 1 or 2 arguments: an array (list or string) and index (optional, if none, operate on last element)."));
+        }
+
+        let length = self.array_length("Remove", &arguments[0])?;
+
+        let index = match arguments.get(1) {
+            None if length == 0 => return Err(self.error("Remove operation requires array to not be empty.")),
+            None => length - 1,
+            Some(Value::NUMBER(index)) => self.array_index(*index, length)?,
+            Some(_) => return Err(self.error("Remove operation requires second argument to be a number."))
+        };
+
+        match arguments[0].clone() {
+            Value::LIST(mut list) => {
+                list.remove(index);
+                Ok(Value::LIST(list))
+            },
+            Value::STRING(string) => Ok(Value::STRING(string.chars().enumerate().filter(|(i, _)| *i != index).map(|(_, character)| character).collect())),
+            _ => Ok(Value::NULL)
+        }
+    }
+
+    pub fn call_replace(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 3 {
+            return Err(self.error("Replace operation requires 3 arguments: an array (list or string), index and value."));
+        }
+
+        let length = self.array_length("Replace", &arguments[0])?;
+
+        let Value::NUMBER(index) = arguments[1] else {
+            return Err(self.error("Replace operation requires second argument to be a number."));
+        };
+
+        let index = self.array_index(index, length)?;
+
+        match (arguments[0].clone(), arguments[2].clone()) {
+            (Value::LIST(mut list), value) => {
+                list[index] = value;
+                Ok(Value::LIST(list))
+            },
+            (Value::STRING(string), Value::STRING(new)) => {
+                let mut characters: Vec<String> = string.chars().map(|character| character.to_string()).collect();
+                characters[index] = new;
+                Ok(Value::STRING(characters.concat()))
+            },
+            _ => Err(self.error("Replace operation requires third argument to be an string if array is a string."))
+        }
+    }
+
+    pub fn call_insert(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 2 && arguments.len() != 3 {
+            return Err(self.error("Insert operation requires 2 or 3 arguments: an array (list or string), value and index (optional, if none, operate on last element)."));
+        }
+
+        let length = self.array_length("Insert", &arguments[0])?;
+
+        // Value can be inserted at the end too.
+        let index = match arguments.get(2) {
+            None => length,
+            Some(Value::NUMBER(index)) => self.array_index(*index, length + 1)?,
+            Some(_) => return Err(self.error("Insert operation requires third argument to be a number."))
+        };
+
+        match (arguments[0].clone(), arguments[1].clone()) {
+            (Value::LIST(mut list), value) => {
+                list.insert(index, value);
+                Ok(Value::LIST(list))
+            },
+            (Value::STRING(string), Value::STRING(inserted)) => {
+                let offset = string.char_indices().nth(index).map_or(string.len(), |(offset, _)| offset);
                 let mut string = string;
-                string.remove(string.len() - 1);
-                Value::STRING(string)
-            } else {
-                self.error("Remove operation requires first argument to be an array (list or string).");
-            }
-        } else if arguments.len() == 2 {
-            if let Value::NUMBER(index) = arguments[1].clone() {
-                let index = index as usize;
-
-                if let Value::LIST(list) = arguments[0].clone() {
-                    let mut list = list;
-                    list.remove(index);
-                    Value::LIST(list)
-                } else if let Value::STRING(string) = arguments[0].clone() {
-                    let mut string = string;
-                    string.remove(index);
-                    Value::STRING(string)
-                } else {
-                    self.error("Remove operation requires first argument to be an array (list or string).");
-                }
-            } else {
-                self.error("Remove operation requires second argument to be a number.");
-            }
-        } else {
-            self.error("Remove operation requires 1 or 2 arguments: an array (list or string) and index (optional, if none, operate on last element).");
+                string.insert_str(offset, &inserted);
+                Ok(Value::STRING(string))
+            },
+            _ => Err(self.error("Insert operation requires second argument to be a string when array is a string."))
+        }
+    }
+
+    /// Returns number of elements of an array (list or string) passed to an operation.
+    fn array_length(&self, operation: &str, array: &Value) -> Result<usize, HierError> {
+        match array {
+            Value::LIST(list) => Ok(list.len()),
+            Value::STRING(string) => Ok(string.chars().count()),
+            _ => Err(self.error(&format!("{} operation requires first argument to be an array (list or string).", operation)))
+        }
+    }
+
+    /// Checks that index is within an array with given number of elements.
+    fn array_index(&self, index: f64, length: usize) -> Result<usize, HierError> {
+        if index < 0f64 || index.fract() != 0f64 || index >= length as f64 {
+            return Err(self.error(&format!("Index {} is out of bounds ({} elements).", index, length)));
         }
+
+        Ok(index as usize)
     }
 
-    pub fn call_replace(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_set(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 3 {
-            self.error("Replace operation requires 3 arguments: an array (list or string), index and value.");
-        }
-        if let Value::NUMBER(index) = arguments[1].clone() {
-            let index = index as usize;
-
-            if let Value::LIST(list) = arguments[0].clone() {
-                let mut list = list;
-                list[index] = arguments[2].clone();
-                Value::LIST(list)
-            } else if let Value::STRING(string) = arguments[0].clone() {
-                if let Value::STRING(new) = arguments[2].clone() {
-                    let mut string = string;
-                    string.replace_range(index..(index + 1), &new);
-                    Value::STRING(string)
-                } else {
-                    self.error("Replace operation requires third argument to be an string if array is a string.");
-                }
-            } else {
-                self.error("Replace operation requires first argument to be an array (list or string).");
-            }
-        } else {
-            self.error("Replace operation requires second argument to be a number.");
+            return Err(self.error("Set operation requires 3 arguments: a table, key (number, string or boolean) and value."));
+        }
+
+        let mut arguments = arguments.into_iter();
+
+        match (arguments.next(), arguments.next(), arguments.next()) {
+            (Some(Value::TABLE(mut table)), Some(key), Some(value)) if Table::is_key(&key) => {
+                table.insert(key, value);
+                Ok(Value::TABLE(table))
+            },
+            (Some(Value::TABLE(_)), _, _) => Err(self.error("Set operation requires second argument to be a number, string or boolean.")),
+            _ => Err(self.error("Set operation requires first argument to be a table."))
         }
     }
 
-    pub fn call_insert(&mut self, arguments: Vec<Value>) -> Value {
-        if arguments.len() == 2 {
-            if let Value::LIST(list) = arguments[0].clone() {
-                let mut list = list;
-                list.push(arguments[1].clone());
-                Value::LIST(list)
-            } else if let Value::STRING(string) = arguments[0].clone() {
-                if let Value::STRING(appended) = arguments[1].clone() {
-                    let mut string = string;
-                    string.push_str(&appended);
-                    Value::STRING(string)
-                } else {
-                    self.error("Insert operation requires second argument to be a string when array is a string.");
-                }
-            } else {
-                self.error("Insert operation requires first argument to be an array (list or string).");
-            }
-        } else if arguments.len() == 3 {
-            if let Value::NUMBER(index) = arguments[2].clone() {
-                let index = index as usize;
-
-                if let Value::LIST(list) = arguments[0].clone() {
-                    let mut list = list;
-                    list.insert(index, arguments[1].clone());
-                    Value::LIST(list)
-                } else if let Value::STRING(string) = arguments[0].clone() {
-                    if let Value::STRING(appended) = arguments[1].clone() {
-                        let mut string = string;
-                        string.insert_str(index, &appended);
-                        Value::STRING(string)
-                    } else {
-                        self.error("Insert operation requires second argument to be a string when array is a string.");
-                    }
-                } else {
-                    self.error("Insert operation requires first argument to be an array (list or string).");
-                }
+    pub fn call_delete(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 2 {
+            return Err(self.error("Delete operation requires 2 arguments: a table and key (number, string or boolean)."));
+        }
+
+        let mut arguments = arguments.into_iter();
+
+        match (arguments.next(), arguments.next()) {
+            (Some(Value::TABLE(mut table)), Some(key)) if Table::is_key(&key) => {
+                table.remove(&key);
+                Ok(Value::TABLE(table))
+            },
+            (Some(Value::TABLE(_)), _) => Err(self.error("Delete operation requires second argument to be a number, string or boolean.")),
+            _ => Err(self.error("Delete operation requires first argument to be a table."))
+        }
+    }
+
+    pub fn call_has(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() != 2 {
+            return Err(self.error("Has operation requires 2 arguments: a table and key (number, string or boolean)."));
+        }
+
+        match (&arguments[0], &arguments[1]) {
+            (Value::TABLE(table), key) if Table::is_key(key) => Ok(Value::BOOL(table.contains_key(key))),
+            (Value::TABLE(_), _) => Err(self.error("Has operation requires second argument to be a number, string or boolean.")),
+            _ => Err(self.error("Has operation requires first argument to be a table."))
+        }
+    }
+
+    /// Returns entries of the table (for keys, values and entries operations) in insertion order.
+    fn table_entries(&mut self, operation: &str, arguments: Vec<Value>) -> Result<Vec<(Value, Value)>, HierError> {
+        match <[Value; 1]>::try_from(arguments) {
+            Ok([Value::TABLE(table)]) => Ok(table.into_iter().collect()),
+            _ => Err(self.error(&format!("{} operation requires 1 argument that is a table.", operation)))
+        }
+    }
+
+    pub fn call_keys(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let entries = self.table_entries("Keys", arguments)?;
+        Ok(Value::LIST(entries.into_iter().map(|(key, _)| key).collect()))
+    }
+
+    pub fn call_values(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let entries = self.table_entries("Values", arguments)?;
+        Ok(Value::LIST(entries.into_iter().map(|(_, value)| value).collect()))
+    }
+
+    pub fn call_entries(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let entries = self.table_entries("Entries", arguments)?;
+        Ok(Value::LIST(entries.into_iter().map(|(key, value)| Value::KEY_VALUE(Box::new(key), Box::new(value))).collect()))
+    }
+
+    pub fn call_merge(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        let mut table = Table::new();
+
+        for argument in arguments {
+            if let Value::TABLE(merged) = argument {
+                table.extend(merged);
             } else {
-                self.error("Insert operation requires third argument to be a number.");
+                return Err(self.error(&format!("Merge operation's all arguments must be tables, but {} was found.", argument.text_representation())));
             }
-        } else {
-            self.error("Insert operation requires 2 or 3 arguments: an array (list or string), value and index (optional, if none, operate on last element).");
         }
+
+        Ok(Value::TABLE(table))
+    }
+
+    pub fn call_break(&mut self, mut arguments: Vec<Value>) -> Result<Value, HierError> {
+        let label = self.take_label(&mut arguments)?;
+
+        if arguments.len() > 1 {
+            return Err(self.error("Break operation accepts only a label (optional, label: \"name\") and a value of the loop (optional, null by default)."));
+        }
+
+        self.signal = Some(Signal::BREAK(label, arguments.pop().unwrap_or(Value::NULL)));
+
+        Ok(Value::NULL)
     }
 
-    pub fn call_break(&mut self, arguments: Vec<Value>) -> Value {
-        if arguments.len() != 0 {
-            self.error("Break operation requires 0 arguments.");
+    pub fn call_continue(&mut self, mut arguments: Vec<Value>) -> Result<Value, HierError> {
+        let label = self.take_label(&mut arguments)?;
+
+        if !arguments.is_empty() {
+            return Err(self.error("Continue operation requires 0 arguments or a label (label: \"name\")."));
         }
 
-        Value::ERROR("LoopExit".to_string())
+        self.signal = Some(Signal::CONTINUE(label));
+
+        Ok(Value::NULL)
     }
 
-    pub fn call_round(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_return(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
+        if arguments.len() > 1 {
+            return Err(self.error("Return requires 0 or 1 arguments: a value (optional, null by default)."));
+        }
+
+        self.signal = Some(Signal::RETURN(arguments.into_iter().next().unwrap_or(Value::NULL)));
+
+        Ok(Value::NULL)
+    }
+
+    pub fn call_round(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Round operation requires 1 number argument.");
+            return Err(self.error("Round operation requires 1 number argument."));
         }
 
         if let Value::NUMBER(number) = arguments[0] {
-            Value::NUMBER(number as i64 as f64)
+            Ok(Value::NUMBER(number as i64 as f64))
         } else {
-            self.error("Round operation requires a number argument.");
+            Err(self.error("Round operation requires a number argument."))
         }
     }
 
-    pub fn call_error(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_error(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Error operation requires 1 argument.");
+            return Err(self.error("Error operation requires 1 argument."));
         }
 
-        Value::ERROR(arguments[0].clone().text_representation())
+        Ok(Value::ERROR(arguments[0].clone().text_representation()))
     }
 
-    pub fn call_panic(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_panic(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Panic operation requires 1 argument.");
+            return Err(self.error("Panic operation requires 1 argument."));
         }
 
-        eprintln!("! Panic: {}", arguments[0].clone().text_representation());
+        self.write_error_output(&format!("! Panic: {}\n", arguments[0].clone().text_representation()))?;
         (self.exit_handler)();
+
+        Err(self.error(&format!("Panic: {}", arguments[0].text_representation())))
     }
 
-    pub fn call_eval(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_eval(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 1 {
-            self.error("Evaluate operation requires 1 string argument.");
+            return Err(self.error("Evaluate operation requires 1 string argument."));
         }
 
         if let Value::STRING(code) = arguments[0].clone() {
-            let mut hier = Hier::new(self.path.clone(), self.module_reader, self.exit_handler);
-            hier.run(code)
+            let mut environment = self.new_module(self.path.clone());
+            let mut diagnostics = vec![];
+            let result = Hier::run_in(&mut environment, code, &mut diagnostics);
+            self.diagnostics.append(&mut diagnostics);
+            result
         } else {
-            self.error("Evaluate operation requires a string argument.");
+            Err(self.error("Evaluate operation requires a string argument."))
         }
     }
 
-    pub fn call_get(&mut self, arguments: Vec<Value>) -> Value {
+    pub fn call_get(&mut self, arguments: Vec<Value>) -> Result<Value, HierError> {
         if arguments.len() != 2 && arguments.len() != 1 {
-            self.error("Get operation requires max 2 arguments: object and key (number or string, optional).");
+            return Err(self.error("Get operation requires max 2 arguments: object and key (number or string, optional)."));
         }
 
         if arguments.len() == 1 {
-            return arguments[0].clone();
+            return Ok(arguments[0].clone());
+        }
+
+        // Tables are indexed by values of any type, so t[1] and t["1"] are different keys.
+        if let Value::TABLE(table) = &arguments[0] {
+            return Ok(table.get(&arguments[1]).cloned().unwrap_or(Value::NULL));
         }
 
         if let Value::STRING(property) = arguments[1].clone() {
             match arguments[0].clone() {
                 Value::KEY_VALUE(key, value) => {
                     if property == "value" {
-                        *value
+                        Ok(*value)
                     } else if property == "key" {
-                        Value::STRING(key)
+                        Ok(*key)
                     } else {
-                        Value::NULL
+                        Ok(Value::NULL)
                     }
                 },
-                Value::TABLE(table) => {
-                    if table.contains_key(&property) {
-                        table.get(&property).unwrap().clone()
-                    } else {
-                        Value::NULL
-                    }
-                },
-                _ => Value::NULL
+                Value::FOREIGN(_, _) => self.get_foreign_property(&arguments[0], &property),
+                _ => Ok(Value::NULL)
             }
         } else if let Value::NUMBER(index) = arguments[1] {
             match arguments[0].clone() {
                 Value::LIST(value) => {
                     if index < 0f64 || value.len() <= index as usize {
-                        self.error(&format!("Index {} is out of bounds ({} elements).", index, value.len()));
+                        return Err(self.error(&format!("Index {} is out of bounds ({} elements).", index, value.len())));
                     }
 
-                    value[index as usize].clone()
+                    Ok(value[index as usize].clone())
                 },
                 Value::STRING(value) => {
-                    if index < 0f64 || value.len() <= index as usize {
-                        self.error(&format!("Index {} is out of bounds ({} elements).", index, value.len()));
-                    }
-                    Value::STRING(value.chars().nth(index as usize).clone().unwrap_or(' ').to_string())
+                    let index = self.array_index(index, value.chars().count())?;
+
+                    Ok(Value::STRING(value.chars().skip(index).take(1).collect()))
                 },
-                _ => if index == 0f64 { arguments[0].clone() } else { Value::NULL },
+                _ => if index == 0f64 { Ok(arguments[0].clone()) } else { Ok(Value::NULL) },
             }
         } else {
-            self.error("Get operation requires second arguments to be a number or string.");
+            Err(self.error("Get operation requires second arguments to be a number or string."))
         }
     }
 }
\ No newline at end of file
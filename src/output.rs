@@ -0,0 +1,46 @@
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+/// Writer shared by environments, through which all printing happens.
+pub type Sink = Rc<RefCell<dyn Write>>;
+
+pub fn stdout_sink() -> Sink {
+    Rc::new(RefCell::new(io::stdout()))
+}
+
+pub fn stderr_sink() -> Sink {
+    Rc::new(RefCell::new(io::stderr()))
+}
+
+/// Sink that keeps everything written to it in memory. Clones share the same contents.
+#[derive(Clone, Default)]
+pub struct Buffer {
+    contents: Rc<RefCell<Vec<u8>>>
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.contents.borrow()).to_string()
+    }
+
+    pub fn clear(&self) {
+        self.contents.borrow_mut().clear();
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
\ No newline at end of file
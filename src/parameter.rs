@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use crate::expression::Expression;
+use crate::value::Value;
+
+/// Parameter of a user defined function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    /// Value used when the argument isn't provided (evaluated when the function is defined). Parameters without it are required.
+    pub default: Option<Value>,
+    /// Rest parameter (...name), which receives a list of all remaining arguments.
+    pub is_rest: bool
+}
+
+impl Parameter {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            default: None,
+            is_rest: false
+        }
+    }
+
+    /// Parses parameters of (| ...) list: identifiers, key values with default values (name: value) and a rest parameter
+    /// (...name) at the end. Returns them with expressions of their default values, which are left for the caller to evaluate.
+    pub fn parse(expressions: &[Expression]) -> Result<Vec<(Parameter, Option<&Expression>)>, String> {
+        let mut parameters: Vec<(Parameter, Option<&Expression>)> = vec![];
+
+        for expression in expressions {
+            if let Some((last, _)) = parameters.last() {
+                if last.is_rest {
+                    return Err(format!("Rest parameter {} must be the last parameter.", last.name));
+                }
+            }
+
+            let parameter = match expression {
+                Expression::IDENTIFIER(name, _, _) => {
+                    if let Some(name) = name.strip_prefix("...") {
+                        (Parameter { name: name.to_string(), default: None, is_rest: true }, None)
+                    } else {
+                        if let Some((last, _)) = parameters.iter().find(|(parameter, _)| parameter.default.is_some()) {
+                            return Err(format!("Parameter {} without a default value must not follow parameter {} with one.", name, last.name));
+                        }
+
+                        (Parameter::new(name.clone()), None)
+                    }
+                },
+                Expression::KEY_VALUE(name, _, _) if name.starts_with("...") => return Err(format!("Rest parameter {} must not have a default value.", &name[3..])),
+                // Default value is a placeholder, until the expression is evaluated.
+                Expression::KEY_VALUE(name, default, _) => (Parameter { name: name.clone(), default: Some(Value::NULL), is_rest: false }, Some(&**default)),
+                _ => return Err("Function arguments must be identifiers.".to_string())
+            };
+
+            parameters.push(parameter);
+        }
+
+        Ok(parameters)
+    }
+
+    /// Returns description of the accepted number of arguments (for example "2", "1 to 3" or "at least 1").
+    pub fn arity(parameters: &[Parameter]) -> String {
+        let required = parameters.iter().filter(|parameter| parameter.default.is_none() && !parameter.is_rest).count();
+        let positional = parameters.iter().filter(|parameter| !parameter.is_rest).count();
+
+        if parameters.iter().any(|parameter| parameter.is_rest) {
+            format!("at least {}", required)
+        } else if required == positional {
+            required.to_string()
+        } else {
+            format!("{} to {}", required, positional)
+        }
+    }
+
+    /// Checks if parameters accept given number of arguments.
+    pub fn accepts(parameters: &[Parameter], count: usize) -> bool {
+        let required = parameters.iter().filter(|parameter| parameter.default.is_none() && !parameter.is_rest).count();
+        let positional = parameters.iter().filter(|parameter| !parameter.is_rest).count();
+
+        count >= required && (count <= positional || parameters.iter().any(|parameter| parameter.is_rest))
+    }
+
+    /// Binds arguments of a call of function with given name to parameters, returning values of their variables. Arguments at
+    /// indices in named are key values passed by name, bound to parameters with the same names.
+    pub fn bind(name: &str, parameters: Vec<Parameter>, arguments: Vec<Value>, named: &[usize]) -> Result<Vec<(String, Value)>, String> {
+        let mut positional: Vec<Value> = Vec::new();
+        let mut by_name: Vec<(String, Value)> = Vec::new();
+
+        for (i, argument) in arguments.into_iter().enumerate() {
+            match argument {
+                Value::KEY_VALUE(key, value) if named.contains(&i) => by_name.push((key.text_representation(), *value)),
+                argument => positional.push(argument)
+            }
+        }
+
+        let count = positional.len() + by_name.len();
+        let arity_error = || format!("Function {} expects {} arguments, but {} were provided.", name, Self::arity(&parameters), count);
+
+        if by_name.is_empty() && !Self::accepts(&parameters, count) {
+            return Err(arity_error());
+        }
+
+        let mut values: Vec<Option<Value>> = vec![None; parameters.len()];
+        let mut positional = positional.into_iter();
+
+        for (parameter, value) in parameters.iter().zip(values.iter_mut()) {
+            *value = if parameter.is_rest {
+                Some(Value::LIST(positional.by_ref().collect()))
+            } else {
+                positional.next()
+            };
+        }
+
+        if positional.next().is_some() {
+            return Err(arity_error());
+        }
+
+        for (key, value) in by_name {
+            let Some(index) = parameters.iter().position(|parameter| parameter.name == key && !parameter.is_rest) else {
+                return Err(format!("Function {} has no parameter named {}.", name, key));
+            };
+
+            if values[index].is_some() {
+                return Err(format!("Argument {} of function {} was provided more than once.", key, name));
+            }
+
+            values[index] = Some(value);
+        }
+
+        parameters.into_iter().zip(values).map(|(parameter, value)| match value.or(parameter.default) {
+            Some(value) => Ok((parameter.name, value)),
+            None => Err(format!("Function {} is missing argument {}.", name, parameter.name))
+        }).collect()
+    }
+
+    /// Splits arguments passed by name from the others, for native functions accepting named arguments. Arguments at indices in
+    /// named (see Environment::named_argument_indices) are key values passed by name, other key values are positional.
+    pub fn split_named(arguments: Vec<Value>, named: &[usize]) -> Result<(Vec<Value>, HashMap<String, Value>), String> {
+        let mut positional: Vec<Value> = Vec::new();
+        let mut by_name: HashMap<String, Value> = HashMap::new();
+
+        for (i, argument) in arguments.into_iter().enumerate() {
+            match argument {
+                Value::KEY_VALUE(key, value) if named.contains(&i) => {
+                    let key = key.text_representation();
+
+                    if by_name.contains_key(&key) {
+                        return Err(format!("Argument {} was provided more than once.", key));
+                    }
+
+                    by_name.insert(key, *value);
+                },
+                argument => positional.push(argument)
+            }
+        }
+
+        Ok((positional, by_name))
+    }
+}
\ No newline at end of file
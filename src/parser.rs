@@ -1,29 +1,43 @@
-use crate::report;
-
-use crate::expression::Expression;
+use std::rc::Rc;
+use crate::diagnostic::Diagnostic;
+use crate::expression::{Block, Expression};
 use crate::interpolated_string::InterpolatedString;
-use crate::location::Location;
+use crate::location::{Location, Span};
 use crate::token::Token;
 use crate::value::Value;
 
+/// Names of get and set functions called by desugared properties, subscripts and property assignments. Identifiers in the code
+/// can't contain dots, so functions declared as get and set don't shadow them.
+pub(crate) const GET_FUNCTION: &str = ".get";
+pub(crate) const SET_FUNCTION: &str = ".set";
+
 pub struct Parser {
     pub code: Expression,
     current_index: usize,
     tokens: Vec<Token>,
-    had_error: bool,
-    pub module_reader: fn(String) -> String,
-    pub exit_handler: fn() -> !
+    /// Span of each token in tokens.
+    spans: Vec<Span>,
+    pub diagnostics: Vec<Diagnostic>,
+    had_error: bool
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>, module_reader: fn(String) -> String, exit_handler: fn() -> !) -> Self {
+    /// Creates the parser, which reports diagnostics at locations of the tokens.
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let spans = tokens.iter().map(|token| Span::at(token.get_location().clone())).collect();
+
+        Self::with_spans(tokens, spans)
+    }
+
+    /// Creates the parser, which reports diagnostics spanning whole tokens. Spans are usually taken from the tokenizer.
+    pub fn with_spans(tokens: Vec<Token>, spans: Vec<Span>) -> Self {
         Self {
             code: Expression::NUMBER(0.0, Location::empty()),
             current_index: 0,
             tokens,
-            had_error: false,
-            module_reader,
-            exit_handler
+            spans,
+            diagnostics: vec![],
+            had_error: false
         }
     }
 
@@ -42,25 +56,25 @@ impl Parser {
 
             match current_token {
                 Token::LEFT_BRACKET(location) => current_list.push(Expression::LIST(self.parse_list(), location)),
-                Token::RIGHT_BRACKET(_) => return current_list,
-                Token::LEFT_CURLY(location) => current_list.push(Expression::BLOCK(self.parse_block(), location)),
-                Token::RIGHT_CURLY(_) => report("Unexpected }.", (*current_token.get_location()).clone()),
-                Token::STRING(string, location) => current_list.push(Expression::STRING(InterpolatedString::construct(string.clone(), Location::empty(), self.module_reader, self.exit_handler), location)),
-                Token::NUMBER(number, location) => current_list.push(Expression::NUMBER(number.clone(), location)),
+                Token::RIGHT_BRACKET(_) => return self.property_assignment(current_list),
+                Token::LEFT_CURLY(location) => current_list.push(Expression::BLOCK(Rc::new(Block::new(self.parse_block())), location)),
+                Token::RIGHT_CURLY(_) => self.error("Unexpected }.", self.previous_span()),
+                Token::STRING(string, location) => current_list.push(self.string(string, location, self.previous_span())),
+                Token::NUMBER(number, location) => current_list.push(Expression::NUMBER(number, location)),
                 Token::IDENTIFIER(identifier, location) => {
                     let result = self.parse_identifier(identifier, location, &mut current_list, true);
                     current_list.push(result);
                 },
                 Token::DOT(_) => {
                     if let Some(last_expression) = current_list.pop() {
-                        let current_token = self.consume();
+                        let current_token = self.consume().clone();
                         if let Token::IDENTIFIER(identifier, location) = current_token {
                             current_list.push(Expression::PROPERTY(Box::new(last_expression), identifier.to_string(), location.clone()));
                         } else {
-                            report(&format!("Key can only be an identifier, but {} was found.", current_token), (*current_token.get_location()).clone());
+                            self.error(&format!("Key can only be an identifier, but {} was found.", current_token), self.previous_span());
                         }
                     } else {
-                        report("Dot must be preceded by a expression.", (*current_token.get_location()).clone());
+                        self.error("Dot must be preceded by a expression.", self.previous_span());
                     }
                 },
                 Token::LEFT_SQUARE(ref location) => {
@@ -69,31 +83,31 @@ impl Parser {
 
                         let mut key_expression = Expression::NUMBER(0.0, Location::empty());
                         if let Token::LEFT_CURLY(location) = current_token {
-                            key_expression = Expression::BLOCK(self.parse_block(), location.clone());
+                            key_expression = Expression::BLOCK(Rc::new(Block::new(self.parse_block())), location.clone());
                         } else if let Token::LEFT_BRACKET(location) = current_token {
                             key_expression = Expression::LIST(self.parse_list(), location.clone());
                         } else if let Token::STRING(string, location) = current_token {
-                            key_expression = Expression::STRING(InterpolatedString::construct(string.clone(), Location::empty(), self.module_reader, self.exit_handler), location.clone());
+                            key_expression = self.string(string, location.clone(), self.previous_span());
                         } else if let Token::NUMBER(number, location) = current_token {
-                            key_expression = Expression::NUMBER(number.clone(), location.clone());
+                            key_expression = Expression::NUMBER(number, location.clone());
                         } else if let Token::IDENTIFIER(identifier, location) = current_token {
-                            key_expression = Expression::IDENTIFIER(identifier.clone(), location.clone());
+                            key_expression = Expression::IDENTIFIER(identifier.clone(), location.clone(), None);
                         } else {
-                            report(&format!("Token {} is disallowed in subscript.", current_token), (*current_token.get_location()).clone());
+                            self.error(&format!("Token {} is disallowed in subscript.", current_token), self.previous_span());
                         }
 
-                        let end = self.consume();
+                        let end = self.consume().clone();
                         if let Token::RIGHT_SQUARE(_) = end { } else {
-                            report("Subscript must end with ].", (*end.get_location()).clone());
+                            self.error("Subscript must end with ].", self.previous_span());
                         }
 
-                        current_list.push(Expression::LIST(vec![Expression::IDENTIFIER("get".to_string(), location.clone()), last_expression, key_expression], location.clone()))
+                        current_list.push(Expression::LIST(vec![Expression::IDENTIFIER(GET_FUNCTION.to_string(), location.clone(), None), last_expression, key_expression], location.clone()))
                     } else {
-                        report("Subscript must be preceded by a expression.", (current_token.clone().get_location()).clone());
+                        self.error("Subscript must be preceded by a expression.", self.previous_span());
                     }
                 },
-                Token::RIGHT_SQUARE(_) => report("Unexpected ].", (*current_token.get_location()).clone()),
-                Token::COLON(_) => report("Unexpected :.", (*current_token.get_location()).clone()),
+                Token::RIGHT_SQUARE(_) => self.error("Unexpected ].", self.previous_span()),
+                Token::COLON(_) => self.error("Unexpected :.", self.previous_span()),
             }
         }
 
@@ -108,11 +122,11 @@ impl Parser {
 
             match current_token {
                 Token::LEFT_BRACKET(location) => current_list.push(Expression::LIST(self.parse_list(), location)),
-                Token::RIGHT_BRACKET(_) => report("Unexpected ).", (*current_token.get_location()).clone()),
-                Token::LEFT_CURLY(location) => current_list.push(Expression::BLOCK(self.parse_block(), location)),
+                Token::RIGHT_BRACKET(_) => self.error("Unexpected ).", self.previous_span()),
+                Token::LEFT_CURLY(location) => current_list.push(Expression::BLOCK(Rc::new(Block::new(self.parse_block())), location)),
                 Token::RIGHT_CURLY(_) => return current_list,
-                Token::STRING(string, location) => current_list.push(Expression::STRING(InterpolatedString::construct(string.clone(), Location::empty(), self.module_reader, self.exit_handler), location)),
-                Token::NUMBER(number, location) => current_list.push(Expression::NUMBER(number.clone(), location)),
+                Token::STRING(string, location) => current_list.push(self.string(string, location, self.previous_span())),
+                Token::NUMBER(number, location) => current_list.push(Expression::NUMBER(number, location)),
                 Token::IDENTIFIER(identifier, location) => {
                     let result = self.parse_identifier(identifier, location, &mut current_list, false);
                     current_list.push(result);
@@ -126,10 +140,10 @@ impl Parser {
                         if let Token::IDENTIFIER(identifier, location) = current_token {
                             current_list.push(Expression::PROPERTY(Box::new(last_expression), identifier, location));
                         } else {
-                            report(&format!("Key can only be an identifier, but {} was found.", current_token), (*current_token.get_location()).clone());
+                            self.error(&format!("Key can only be an identifier, but {} was found.", current_token), self.previous_span());
                         }
                     } else {
-                        report("Dot must be preceded by a expression.", (*current_token.get_location()).clone());
+                        self.error("Dot must be preceded by a expression.", self.previous_span());
                     }
                 },
                 Token::LEFT_SQUARE(ref location) => {
@@ -138,31 +152,31 @@ impl Parser {
 
                         let mut key_expression = Expression::NUMBER(0.0, Location::empty());
                         if let Token::LEFT_CURLY(location) = current_token {
-                            key_expression = Expression::BLOCK(self.parse_block(), location.clone());
+                            key_expression = Expression::BLOCK(Rc::new(Block::new(self.parse_block())), location.clone());
                         } else if let Token::LEFT_BRACKET(location) = current_token {
                             key_expression = Expression::LIST(self.parse_list(), location.clone());
                         } else if let Token::STRING(string, location) = current_token {
-                            key_expression = Expression::STRING(InterpolatedString::construct(string.clone(), Location::empty(), self.module_reader, self.exit_handler), location.clone());
+                            key_expression = self.string(string, location.clone(), self.previous_span());
                         } else if let Token::NUMBER(number, location) = current_token {
-                            key_expression = Expression::NUMBER(number.clone(), location.clone());
+                            key_expression = Expression::NUMBER(number, location.clone());
                         } else if let Token::IDENTIFIER(identifier, location) = current_token {
-                            key_expression = Expression::IDENTIFIER(identifier.clone(), location.clone());
+                            key_expression = Expression::IDENTIFIER(identifier.clone(), location.clone(), None);
                         } else {
-                            report(&format!("Token {} is disallowed in subscript.", current_token), (*current_token.get_location()).clone());
+                            self.error(&format!("Token {} is disallowed in subscript.", current_token), self.previous_span());
                         }
 
-                        let end = self.consume();
+                        let end = self.consume().clone();
                         if let Token::RIGHT_SQUARE(_) = end { } else {
-                            report("Subscript must end with ].", (*end.get_location()).clone());
+                            self.error("Subscript must end with ].", self.previous_span());
                         }
 
-                        current_list.push(Expression::LIST(vec![Expression::IDENTIFIER("get".to_string(), location.clone()), last_expression, key_expression], location.clone()))
+                        current_list.push(Expression::LIST(vec![Expression::IDENTIFIER(GET_FUNCTION.to_string(), location.clone(), None), last_expression, key_expression], location.clone()))
                     } else {
-                        report("Subscript must be preceded by a expression.", (current_token.clone().get_location()).clone());
+                        self.error("Subscript must be preceded by a expression.", self.previous_span());
                     }
                 },
-                Token::RIGHT_SQUARE(_) => report("Unexpected ].", (*current_token.get_location()).clone()),
-                Token::COLON(_) => report("Unexpected :.", (*current_token.get_location()).clone()),
+                Token::RIGHT_SQUARE(_) => self.error("Unexpected ].", self.previous_span()),
+                Token::COLON(_) => self.error("Unexpected :.", self.previous_span()),
             }
         }
 
@@ -172,21 +186,19 @@ impl Parser {
     pub fn parse_expression(&mut self) -> Expression {
         let current_token = self.consume().clone();
 
-        let expression = match current_token {
+        match current_token {
             Token::LEFT_BRACKET(location) => Expression::LIST(self.parse_list(), location),
-            Token::RIGHT_BRACKET(_) => { report("Unexpected ).", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-            Token::LEFT_CURLY(location) => Expression::BLOCK(self.parse_block(), location),
-            Token::RIGHT_CURLY(_) => { report("Unexpected }.", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-            Token::STRING(string, location) => Expression::STRING(InterpolatedString::construct(string.clone(), Location::empty(), self.module_reader, self.exit_handler), location),
-            Token::NUMBER(number, location) => Expression::NUMBER(number.clone(), location),
+            Token::RIGHT_BRACKET(_) => { self.error("Unexpected ).", self.previous_span()); Expression::VALUE(Value::NULL) },
+            Token::LEFT_CURLY(location) => Expression::BLOCK(Rc::new(Block::new(self.parse_block())), location),
+            Token::RIGHT_CURLY(_) => { self.error("Unexpected }.", self.previous_span()); Expression::VALUE(Value::NULL) },
+            Token::STRING(string, location) => self.string(string, location, self.previous_span()),
+            Token::NUMBER(number, location) => Expression::NUMBER(number, location),
             Token::IDENTIFIER(identifier, location) => self.parse_identifier(identifier, location, &mut vec![], false),
-            Token::DOT(_) => { report("Unexpected ..", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-            Token::LEFT_SQUARE(_) => { report("Unexpected [.", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-            Token::RIGHT_SQUARE(_) => { report("Unexpected ].", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-            Token::COLON(_) => { report("Unexpected :.", (*current_token.get_location()).clone()); Expression::VALUE(Value::NULL) },
-        };
-
-        expression
+            Token::DOT(_) => { self.error("Unexpected ..", self.previous_span()); Expression::VALUE(Value::NULL) },
+            Token::LEFT_SQUARE(_) => { self.error("Unexpected [.", self.previous_span()); Expression::VALUE(Value::NULL) },
+            Token::RIGHT_SQUARE(_) => { self.error("Unexpected ].", self.previous_span()); Expression::VALUE(Value::NULL) },
+            Token::COLON(_) => { self.error("Unexpected :.", self.previous_span()); Expression::VALUE(Value::NULL) },
+        }
     }
 
     fn parse_identifier(&mut self, identifier: String, location: Location, current_list: &mut Vec<Expression>, is_list: bool) -> Expression {
@@ -200,22 +212,22 @@ impl Parser {
             The piping syntax is converted into the first example so it has the same effect.
             */
 
-            if current_list.len() == 0 && is_list {
+            if current_list.is_empty() && is_list {
                 // Current list is empty when the identifier is the first element of the list, which means that it should be a name for function > (more than).
-                return Expression::IDENTIFIER(identifier.clone().to_string(), location);
-            } else if current_list.len() == 0 && !is_list {
-                report("Unexpected pipe operator (>). It should be placed after a list.", Location::empty());
+                return Expression::IDENTIFIER(identifier.clone().to_string(), location, None);
+            } else if current_list.is_empty() && !is_list {
+                self.error("Unexpected pipe operator (>). It should be placed after a list.", self.previous_span());
                 return Expression::VALUE(Value::NULL);
             }
 
             let last_expression = current_list[current_list.len() - 1].clone();
             current_list.remove(current_list.len() - 1);
 
-            let next_token = self.consume();
+            let next_token = self.consume().clone();
 
-            match next_token {
-                Token::LEFT_BRACKET(_) => { }
-                _ => report("There must be a list after the pipe operator (>).", next_token.get_location().clone())
+            if !matches!(next_token, Token::LEFT_BRACKET(_)) {
+                self.error("There must be a list after the pipe operator (>).", self.previous_span());
+                return Expression::VALUE(Value::NULL);
             }
 
             let mut next_expression = self.parse_list();
@@ -223,17 +235,85 @@ impl Parser {
             next_expression.insert(1, last_expression);
 
             Expression::LIST(next_expression, location)
+        } else if identifier.starts_with("...") && !(is_list && matches!(current_list.first(), Some(Expression::IDENTIFIER(name, _, _)) if name == "|")) {
+            self.error(&format!("Rest parameter {} can be only declared in a function arguments list (| ...).", identifier), self.previous_span());
+            Expression::VALUE(Value::NULL)
         } else {
             if let Token::COLON(_) = self.peek().clone() {
                 self.consume();
                 let value = self.parse_expression();
                 Expression::KEY_VALUE(identifier.to_string().clone(), Box::new(value), location)
             } else {
-                Expression::IDENTIFIER(identifier.clone().to_string(), location)
+                Expression::IDENTIFIER(identifier.clone().to_string(), location, None)
             }
         }
     }
 
+    /// Desugars assignment to a property of a table (=table.key value) to (=table (set table "key" value)). For nested properties
+    /// (=table.a.b value), inner tables are read using get and set in the outer ones. Both are called by their internal names.
+    fn property_assignment(&mut self, list: Vec<Expression>) -> Vec<Expression> {
+        let Some(Expression::PROPERTY(_, _, location)) = list.first() else { return list };
+        let location = location.clone();
+
+        let mut keys: Vec<String> = vec![];
+        let mut target = &list[0];
+
+        while let Expression::PROPERTY(expression, key, _) = target {
+            keys.insert(0, key.clone());
+            target = expression;
+        }
+
+        let name = match target {
+            Expression::IDENTIFIER(name, _, _) if name.starts_with('=') && name.len() > 1 => name[1..].to_string(),
+            _ => return list
+        };
+
+        if list.len() != 2 {
+            let span = Span::new(location.clone(), self.previous_span().end);
+            self.error("Property assignment requires 1 value.", span);
+            return list;
+        }
+
+        let value = list.into_iter().nth(1).unwrap_or(Expression::VALUE(Value::NULL));
+        let table = Expression::IDENTIFIER(name.clone(), location.clone(), None);
+        let assigned = self.set_property(table, &keys, value, &location);
+
+        vec![Expression::IDENTIFIER(format!("={}", name), location, None), assigned]
+    }
+
+    fn set_property(&mut self, table: Expression, keys: &[String], value: Expression, location: &Location) -> Expression {
+        let key = self.string(keys[0].clone(), location.clone(), Span::at(location.clone()));
+
+        let value = if keys.len() > 1 {
+            let inner = Expression::LIST(vec![Expression::IDENTIFIER(GET_FUNCTION.to_string(), location.clone(), None), table.clone(), key.clone()], location.clone());
+            self.set_property(inner, &keys[1..], value, location)
+        } else {
+            value
+        };
+
+        Expression::LIST(vec![Expression::IDENTIFIER(SET_FUNCTION.to_string(), location.clone(), None), table, key, value], location.clone())
+    }
+
+    fn string(&mut self, string: String, location: Location, span: Span) -> Expression {
+        let (string, diagnostics) = InterpolatedString::construct(string, span);
+        self.diagnostics.extend(diagnostics);
+        Expression::STRING(string, location)
+    }
+
+    fn error(&mut self, message: &str, span: Span) {
+        self.diagnostics.push(Diagnostic::error(message.to_string(), span));
+        self.had_error = true;
+    }
+
+    /// Span of the last consumed token.
+    fn previous_span(&self) -> Span {
+        self.span(self.current_index - 1)
+    }
+
+    fn span(&self, index: usize) -> Span {
+        self.spans.get(index).cloned().unwrap_or_else(|| Span::at(self.tokens[index].get_location().clone()))
+    }
+
     fn consume(&mut self) -> &Token {
         let token = &self.tokens[self.current_index];
         self.current_index += 1;
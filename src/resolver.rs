@@ -0,0 +1,325 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+use crate::diagnostic::Diagnostic;
+use crate::expression::{Block, Expression};
+use crate::location::{Location, Span};
+use crate::types::Type;
+
+/// Scope known to the resolver, mirroring a scope that the interpreter will create.
+#[derive(Default)]
+struct ResolverScope {
+    variables: HashSet<String>,
+    /// Variables that code the resolver can't follow may declare in this scope, so their lookups are left unresolved.
+    unknown: HashSet<String>,
+    /// Uses of variables that weren't declared yet, waiting for a declaration in this scope.
+    pending: Vec<(String, Location)>,
+    /// Code in the scope runs in a scope unknown to the resolver, so lookups can't continue past it.
+    is_opaque: bool,
+    /// Outermost scope of a function (or module). Uses in it don't wait for declarations in enclosing scopes, because they run later.
+    is_function: bool
+}
+
+/// Pass between parsing and interpreting, which annotates identifiers with depth of the scope declaring them (number of scopes
+/// between the use and the declaration), so the interpreter doesn't have to look them up by name in every scope. It also reports
+/// duplicate declarations and uses of variables before their declaration.
+///
+/// Identifiers that can't be resolved statically (declared by the host, by previous runs or in blocks passed to other functions)
+/// are left unresolved and looked up by name.
+pub struct Resolver {
+    scopes: Vec<ResolverScope>,
+    pub diagnostics: Vec<Diagnostic>,
+    allow_redeclaration: bool
+}
+
+impl Resolver {
+    pub fn new(allow_redeclaration: bool) -> Self {
+        Self {
+            scopes: vec![],
+            diagnostics: vec![],
+            allow_redeclaration
+        }
+    }
+
+    pub fn resolve(&mut self, code: &mut Expression) {
+        self.begin_scope(false, true);
+
+        if let Expression::BLOCK(block, _) = code {
+            self.expressions(Self::block(block));
+        } else {
+            self.expression(code);
+        }
+
+        self.end_scope();
+    }
+
+    fn expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::IDENTIFIER(name, location, depth) => *depth = self.lookup(name, location),
+            Expression::LIST(list, _) => self.list(list),
+            Expression::BLOCK(block, _) => self.opaque_block(Self::block(block)),
+            Expression::PROPERTY(expression, _, _) => self.expression(expression),
+            Expression::KEY_VALUE(_, expression, _) => self.expression(expression),
+            Expression::STRING(_, _) | Expression::NUMBER(_, _) | Expression::VALUE(_) => {}
+        }
+    }
+
+    fn expressions(&mut self, expressions: &mut [Expression]) {
+        for expression in expressions {
+            self.expression(expression);
+        }
+    }
+
+    fn list(&mut self, list: &mut [Expression]) {
+        let name = match list.first() {
+            Some(Expression::IDENTIFIER(name, _, _)) => name.clone(),
+            Some(Expression::LIST(head, _)) if Self::is_parameters(head) && list.len() == 2 => {
+                // Anonymous function.
+                return self.function(list);
+            },
+            // Otherwise, it is a list or a call on an object, which evaluates all of its elements.
+            _ => return self.arguments(list)
+        };
+
+        if self.is_declared(&name) {
+            // Function declared in the code shadows the built-in one, so its blocks aren't run in scopes known to the resolver.
+            return self.arguments(&mut list[1..]);
+        }
+
+        let (head, arguments) = list.split_at_mut(1);
+
+        let arguments = if matches!(&name as &str, "while" | "for" | "repeat") {
+            Self::skip_label(arguments)
+        } else {
+            arguments
+        };
+
+        match &name as &str {
+            "|" => {
+                for argument in arguments {
+                    if let Expression::KEY_VALUE(_, default, _) = argument {
+                        self.expression(default);
+                    }
+                }
+            },
+            "if" => {
+                for (i, argument) in arguments.iter_mut().enumerate() {
+                    match argument {
+                        Expression::BLOCK(block, _) if i > 0 => self.scoped_block(Self::block(block), &[]),
+                        _ => self.expression(argument)
+                    }
+                }
+            },
+            "while" if Self::ends_with_block(arguments) && arguments.len() == 2 => {
+                self.begin_scope(false, false);
+
+                if let Expression::BLOCK(condition, _) = &mut arguments[0] {
+                    self.expressions(Self::block(condition));
+                } else {
+                    self.expression(&mut arguments[0]);
+                }
+
+                self.last_block(arguments, &[]);
+                self.end_scope();
+            },
+            "for" | "repeat" if Self::ends_with_block(arguments) => {
+                let (first, _) = arguments.split_at_mut(arguments.len() - 1);
+                self.arguments(first);
+
+                self.begin_scope(false, false);
+                self.last_block(arguments, if name == "for" { &["element"] } else { &[] });
+                self.end_scope();
+            },
+            "try" | "map" if Self::ends_with_block(arguments) && arguments.len() == 2 => {
+                self.expression(&mut arguments[0]);
+                self.last_block(arguments, if name == "try" { &["error"] } else { &["element"] });
+            },
+            "filter" | "find" | "any" | "all" | "sort_by" | "reduce" | "fold" | "sort" if Self::ends_with_block(arguments) => {
+                let (first, _) = arguments.split_at_mut(arguments.len() - 1);
+                self.arguments(first);
+
+                let variables: &[&str] = match &name as &str {
+                    "reduce" | "fold" => &["accumulator", "element"],
+                    "sort" => &["first", "second"],
+                    _ => &["element"]
+                };
+
+                self.last_block(arguments, variables);
+            },
+            "run" => {
+                for argument in arguments {
+                    if let Expression::BLOCK(block, _) = argument {
+                        self.expressions(Self::block(block));
+                    } else {
+                        self.expression(argument);
+                    }
+                }
+            },
+            _ if (name.starts_with('@') || name.starts_with('=')) && name.len() > 1 => {
+                let is_declaration = name.starts_with('@');
+                let is_function = matches!(arguments, [Expression::LIST(parameters, _), Expression::BLOCK(_, _)] if Self::is_parameters(parameters));
+
+                // Function is declared before its body is resolved, so it can call itself.
+                if is_function {
+                    if is_declaration {
+                        self.declare(&name[1..], &head[0].get_location());
+                    }
+
+                    self.function(arguments);
+                } else {
+                    self.arguments(arguments);
+
+                    if is_declaration {
+                        self.declare(&name[1..], &head[0].get_location());
+                    }
+                }
+            },
+            _ => self.arguments(arguments)
+        }
+    }
+
+    /// Resolves arguments of a function, which aren't run by the resolver (blocks passed to it are opaque).
+    fn arguments(&mut self, arguments: &mut [Expression]) {
+        for argument in arguments {
+            self.expression(argument);
+        }
+    }
+
+    /// Resolves parameters (| a b: 1 ...c) and a block of a function.
+    fn function(&mut self, function: &mut [Expression]) {
+        // Default values are evaluated when the function is defined, in the enclosing scope.
+        if let Expression::LIST(parameters, _) = &mut function[0] {
+            for parameter in &mut parameters[1..] {
+                if let Expression::KEY_VALUE(_, default, _) = parameter {
+                    self.expression(default);
+                }
+            }
+        }
+
+        self.begin_scope(false, true);
+
+        if let Expression::LIST(parameters, _) = &function[0] {
+            for parameter in &parameters[1..] {
+                match parameter {
+                    Expression::IDENTIFIER(name, location, _) => self.declare(name.strip_prefix("...").unwrap_or(name), location),
+                    Expression::KEY_VALUE(name, _, location) => self.declare(name, location),
+                    _ => {}
+                }
+            }
+        }
+
+        if let Expression::BLOCK(block, _) = &mut function[1] {
+            self.expressions(Self::block(block));
+        }
+
+        self.end_scope();
+    }
+
+    /// Resolves a block run in a new scope with given variables declared.
+    fn scoped_block(&mut self, block: &mut [Expression], variables: &[&str]) {
+        self.begin_scope(false, false);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.variables.extend(variables.iter().map(|variable| variable.to_string()));
+        }
+
+        self.expressions(block);
+        self.end_scope();
+    }
+
+    fn last_block(&mut self, arguments: &mut [Expression], variables: &[&str]) {
+        if let Some(Expression::BLOCK(block, _)) = arguments.last_mut() {
+            self.scoped_block(Self::block(block), variables);
+        }
+    }
+
+    /// Resolves a block, which is run by code that the resolver can't follow. Variables it declares may end up in the current scope.
+    fn opaque_block(&mut self, block: &mut [Expression]) {
+        self.begin_scope(true, false);
+        self.expressions(block);
+
+        if let Some(scope) = self.scopes.pop() {
+            if let Some(enclosing) = self.scopes.last_mut() {
+                enclosing.unknown.extend(scope.variables);
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, location: &Location) {
+        let Some(scope) = self.scopes.last_mut() else { return };
+
+        if !scope.variables.insert(name.to_string()) && !self.allow_redeclaration {
+            self.diagnostics.push(Diagnostic::error(format!("Variable '{}' already exists in current scope.", name), Span::at(location.clone())));
+        }
+
+        let (used, pending) = std::mem::take(&mut scope.pending).into_iter().partition(|(pending_name, _)| pending_name == name);
+        scope.pending = pending;
+
+        for (_, location) in used {
+            self.diagnostics.push(Diagnostic::warning(format!("Variable {} is used before it is declared.", name), Span::at(location)));
+        }
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.variables.contains(name))
+    }
+
+    fn lookup(&mut self, name: &str, location: &Location) -> Option<usize> {
+        if matches!(name, "true" | "false" | "null") || name.contains('#') || Type::get_for_name(name).is_some() {
+            return None;
+        }
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.variables.contains(name) {
+                return Some(depth);
+            }
+
+            if scope.unknown.contains(name) || scope.is_opaque {
+                return None;
+            }
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.pending.push((name.to_string(), location.clone()));
+        }
+
+        None
+    }
+
+    fn begin_scope(&mut self, is_opaque: bool, is_function: bool) {
+        self.scopes.push(ResolverScope { is_opaque, is_function, ..ResolverScope::default() });
+    }
+
+    fn end_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            if scope.is_function || scope.is_opaque {
+                return;
+            }
+
+            // Uses not declared in the scope may still be declared later in the enclosing one.
+            if let Some(enclosing) = self.scopes.last_mut() {
+                enclosing.pending.extend(scope.pending);
+            }
+        }
+    }
+
+    fn block(block: &mut Rc<Block>) -> &mut [Expression] {
+        Rc::make_mut(block).expressions_mut()
+    }
+
+    fn is_parameters(list: &[Expression]) -> bool {
+        matches!(list.first(), Some(Expression::IDENTIFIER(name, _, _)) if name == "|")
+    }
+
+    /// Skips label of a loop (label: "name"), which is a string that doesn't need resolving.
+    fn skip_label(arguments: &mut [Expression]) -> &mut [Expression] {
+        if matches!(arguments.first(), Some(Expression::KEY_VALUE(key, _, _)) if key == "label") {
+            &mut arguments[1..]
+        } else {
+            arguments
+        }
+    }
+
+    fn ends_with_block(arguments: &[Expression]) -> bool {
+        matches!(arguments.last(), Some(Expression::BLOCK(_, _)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,484 @@
+use std::fmt::{Display, Formatter};
+use serde::de::{DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+use crate::conversion::ConversionError;
+use crate::table::Table;
+use crate::value::Value;
+
+impl serde::ser::Error for ConversionError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+impl serde::de::Error for ConversionError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self { message: message.to_string() }
+    }
+}
+
+/// Converts any serializable Rust value to a value. Structs and maps become tables, sequences and tuples become lists.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, ConversionError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Converts a value to any deserializable Rust value.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, ConversionError> {
+    T::deserialize(value)
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::NUMBER(number) => serializer.serialize_f64(*number),
+            Value::STRING(string) => serializer.serialize_str(string),
+            Value::BOOL(boolean) => serializer.serialize_bool(*boolean),
+            Value::NULL => serializer.serialize_unit(),
+            Value::LIST(list) => list.serialize(serializer),
+            Value::TABLE(table) => table.serialize(serializer),
+            Value::KEY_VALUE(key, value) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(key, value)?;
+                map.end()
+            },
+            _ => Err(serde::ser::Error::custom(format!("Value of type {} can't be serialized.", self.get_type().text_representation())))
+        }
+    }
+}
+
+impl Serialize for Table {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+
+        for (key, value) in self.iter() {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "a number, string, bool, null, list or table")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::BOOL(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::NUMBER(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::STRING(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::STRING(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::NULL)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::NULL)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut sequence: A) -> Result<Value, A::Error> {
+        let mut list = Vec::new();
+
+        while let Some(value) = sequence.next_element()? {
+            list.push(value);
+        }
+
+        Ok(Value::LIST(list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut table = Table::new();
+
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            if !Table::is_key(&key) {
+                return Err(serde::de::Error::custom(ConversionError::expected("Number, String or Bool (table key)", &key).message));
+            }
+
+            table.insert(key, value);
+        }
+
+        Ok(Value::TABLE(table))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ConversionError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::NUMBER(number) => {
+                // Integers are visited as such, so they can be deserialized into integer types.
+                if number.fract() == 0f64 && number >= i64::MIN as f64 && number <= i64::MAX as f64 {
+                    visitor.visit_i64(number as i64)
+                } else {
+                    visitor.visit_f64(number)
+                }
+            },
+            Value::STRING(string) => visitor.visit_string(string),
+            Value::BOOL(boolean) => visitor.visit_bool(boolean),
+            Value::NULL => visitor.visit_unit(),
+            Value::LIST(list) => visitor.visit_seq(SeqDeserializer::new(list.into_iter())),
+            Value::TABLE(table) => visitor.visit_map(MapDeserializer::new(table.into_iter())),
+            Value::KEY_VALUE(key, value) => visitor.visit_map(MapDeserializer::new(std::iter::once((*key, *value)))),
+            _ => Err(ConversionError::expected("a serializable value", &self))
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        if let Value::NULL = self {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::STRING(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::TABLE(table) if table.len() == 1 => {
+                // We can unwrap, because we checked that the table has one entry.
+                let (variant, value) = table.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            },
+            Value::KEY_VALUE(variant, value) => visitor.visit_enum(EnumDeserializer { variant: *variant, value: *value }),
+            _ => Err(ConversionError::expected("String or Table with one entry (enum variant)", &self))
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: Value,
+    value: Value
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = ConversionError;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Value), ConversionError> {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<(), ConversionError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, ConversionError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_any(visitor)
+    }
+}
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = VariantSerializer<ListSerializer>;
+    type SerializeMap = TableSerializer;
+    type SerializeStruct = TableSerializer;
+    type SerializeStructVariant = VariantSerializer<TableSerializer>;
+
+    fn serialize_bool(self, value: bool) -> Result<Value, ConversionError> {
+        Ok(Value::BOOL(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value as f64))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Value, ConversionError> {
+        Ok(Value::NUMBER(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value, ConversionError> {
+        Ok(Value::STRING(value.to_string()))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Value, ConversionError> {
+        Ok(Value::STRING(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value, ConversionError> {
+        Ok(Value::LIST(value.iter().map(|byte| Value::NUMBER(*byte as f64)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value, ConversionError> {
+        Ok(Value::NULL)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ConversionError> {
+        Ok(Value::NULL)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, ConversionError> {
+        Ok(Value::NULL)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Value, ConversionError> {
+        Ok(Value::STRING(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Value, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<Value, ConversionError> {
+        Ok(Value::TABLE(Table::from([(Value::STRING(variant.to_string()), to_value(value)?)])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, ConversionError> {
+        Ok(ListSerializer { list: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, ConversionError> {
+        Ok(VariantSerializer { variant, serializer: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<TableSerializer, ConversionError> {
+        Ok(TableSerializer { table: Table::new(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<TableSerializer, ConversionError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, ConversionError> {
+        Ok(VariantSerializer { variant, serializer: self.serialize_map(Some(len))? })
+    }
+}
+
+struct ListSerializer {
+    list: Vec<Value>
+}
+
+impl SerializeSeq for ListSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.list.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::LIST(self.list))
+    }
+}
+
+impl SerializeTuple for ListSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for ListSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        SerializeSeq::end(self)
+    }
+}
+
+struct TableSerializer {
+    table: Table,
+    key: Option<Value>
+}
+
+impl SerializeMap for TableSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConversionError> {
+        let key = to_value(key)?;
+
+        if !Table::is_key(&key) {
+            return Err(ConversionError::expected("Number, String or Bool (table key)", &key));
+        }
+
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        let key = self.key.take().ok_or_else(|| serde::ser::Error::custom("Table value was serialized before its key."))?;
+        self.table.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::TABLE(self.table))
+    }
+}
+
+impl SerializeStruct for TableSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConversionError> {
+        self.table.insert(Value::STRING(key.to_string()), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::TABLE(self.table))
+    }
+}
+
+/// Serializes enum variant with data as a table with one entry: name of the variant and its data.
+struct VariantSerializer<S> {
+    variant: &'static str,
+    serializer: S
+}
+
+impl<S> VariantSerializer<S> {
+    fn wrap(variant: &'static str, value: Value) -> Value {
+        Value::TABLE(Table::from([(Value::STRING(variant.to_string()), value)]))
+    }
+}
+
+impl SerializeTupleVariant for VariantSerializer<ListSerializer> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        SerializeSeq::serialize_element(&mut self.serializer, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Self::wrap(self.variant, SerializeSeq::end(self.serializer)?))
+    }
+}
+
+impl SerializeStructVariant for VariantSerializer<TableSerializer> {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConversionError> {
+        SerializeStruct::serialize_field(&mut self.serializer, key, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Self::wrap(self.variant, SerializeStruct::end(self.serializer)?))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,29 @@
+use crate::value::Value;
+
+/// Control flow signal. It isn't an error: it stops evaluation of the code until it reaches the construct handling it.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub enum Signal {
+    /// Returns the value from the innermost function call (or from the module, outside of functions).
+    RETURN(Value),
+    /// Exits the innermost loop or the loop with given label, which evaluates to the value.
+    BREAK(Option<String>, Value),
+    /// Skips to the next iteration of the innermost loop or the loop with given label.
+    CONTINUE(Option<String>)
+}
+
+impl Signal {
+    /// Message of the error when the signal isn't handled before reaching a function call or the end of the module.
+    pub fn error_message(&self) -> String {
+        let (name, label) = match self {
+            Signal::RETURN(_) => return "Return must be used inside of a function.".to_string(),
+            Signal::BREAK(label, _) => ("Break", label),
+            Signal::CONTINUE(label) => ("Continue", label)
+        };
+
+        match label {
+            Some(label) => format!("{} must be used inside of a loop labeled {}.", name, label),
+            None => format!("{} must be used inside of a loop.", name)
+        }
+    }
+}
\ No newline at end of file
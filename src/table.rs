@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::ops::Index;
+use crate::value::Value;
+
+/// Table of values, which keeps its entries in insertion order, so iterating over it is deterministic. Keys are numbers, strings
+/// or booleans.
+#[derive(Clone, Default)]
+pub struct Table {
+    entries: Vec<(Value, Value)>,
+    /// Index of the entry for each key.
+    indices: HashMap<Value, usize>
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the value can be used as a key.
+    pub fn is_key(value: &Value) -> bool {
+        matches!(value, Value::NUMBER(_) | Value::STRING(_) | Value::BOOL(_))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.indices.get(key).map(|&index| &self.entries[index].1)
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Sets value of the key, returning the previous one. A new key is added at the end, an existing one keeps its position.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        if let Some(&index) = self.indices.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[index].1, value));
+        }
+
+        self.indices.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Removes the key, returning its value. Order of the other entries is preserved.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+
+        for (key, _) in &self.entries[index..] {
+            if let Some(index) = self.indices.get_mut(key) {
+                *index -= 1;
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl Debug for Table {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Tables are equal if they have equal values for the same keys, regardless of their order.
+impl PartialEq for Table {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl Eq for Table {
+
+}
+
+/// Indexes the table with a string key.
+impl Index<&str> for Table {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.get(&Value::STRING(key.to_string())).unwrap_or_else(|| panic!("Table has no key {}.", key))
+    }
+}
+
+impl IntoIterator for Table {
+    type Item = (Value, Value);
+    type IntoIter = std::vec::IntoIter<(Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl Extend<(Value, Value)> for Table {
+    fn extend<T: IntoIterator<Item = (Value, Value)>>(&mut self, entries: T) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl FromIterator<(Value, Value)> for Table {
+    fn from_iter<T: IntoIterator<Item = (Value, Value)>>(entries: T) -> Self {
+        let mut table = Table::new();
+        table.extend(entries);
+        table
+    }
+}
+
+impl<const N: usize> From<[(Value, Value); N]> for Table {
+    fn from(entries: [(Value, Value); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
\ No newline at end of file
@@ -1,5 +1,5 @@
-use crate::location::Location;
-use crate::report;
+use crate::diagnostic::Diagnostic;
+use crate::location::{Location, Span};
 use crate::token::Token;
 
 pub struct Tokenizer {
@@ -8,6 +8,9 @@ pub struct Tokenizer {
     current_line: i64,
     current_offset: i64,
     pub tokens: Vec<Token>,
+    /// Span of each token in tokens.
+    pub spans: Vec<Span>,
+    pub diagnostics: Vec<Diagnostic>,
     had_error: bool,
     pub module_name: String
 }
@@ -20,6 +23,8 @@ impl Tokenizer {
             current_line: 1,
             current_offset: 0,
             tokens: vec![],
+            spans: vec![],
+            diagnostics: vec![],
             had_error: false,
             module_name: "main".to_string(),
         }
@@ -32,18 +37,28 @@ impl Tokenizer {
             current_line: 1,
             current_offset: 0,
             tokens: vec![],
+            spans: vec![],
+            diagnostics: vec![],
             had_error: false,
             module_name
         }
     }
 
+    /// Creates a tokenizer for code beginning at the location in a module, for example an interpolation in a string.
+    pub fn new_at(code: String, location: &Location) -> Self {
+        let mut tokenizer = Self::new_with_name(code, location.module.clone());
+        tokenizer.current_line = location.line_number;
+        tokenizer.current_offset = location.offset - 1;
+        tokenizer
+    }
+
     /// Returns bool if there was a error.
     pub fn tokenize_module(&mut self) -> bool {
-        self.tokens.push(Token::LEFT_CURLY(self.make_location()));
+        self.symbol(Token::LEFT_CURLY);
 
         self.tokenize_code();
 
-        self.tokens.push(Token::RIGHT_CURLY(self.make_location()));
+        self.symbol(Token::RIGHT_CURLY);
 
         self.had_error
     }
@@ -74,50 +89,52 @@ impl Tokenizer {
                 continue;
             } else if current_char == ' ' || current_char == '\t' {
                 self.consume();
+            } else if current_char == '.' && self.peek_next() == '.' {
+                self.rest_identifier();
             } else if current_char == '.' {
-                self.tokens.push(Token::DOT(self.make_location()));
+                self.symbol(Token::DOT);
                 self.consume();
             } else if current_char == ':' {
-                self.tokens.push(Token::COLON(self.make_location()));
+                self.symbol(Token::COLON);
                 self.consume();
             } else if current_char == '(' {
-                self.tokens.push(Token::LEFT_BRACKET(self.make_location()));
+                self.symbol(Token::LEFT_BRACKET);
                 count_of_brackets += 1;
                 self.consume();
             } else if current_char == ')' {
-                self.tokens.push(Token::RIGHT_BRACKET(self.make_location()));
+                self.symbol(Token::RIGHT_BRACKET);
                 count_of_brackets -= 1;
 
                 if count_of_brackets == -1 {
-                    report("Unexpected ).", self.make_location());
+                    self.error("Unexpected ).", self.make_location());
                 }
 
                 self.consume();
             } else if current_char == '[' {
                 count_of_squares += 1;
-                self.tokens.push(Token::LEFT_SQUARE(self.make_location()));
+                self.symbol(Token::LEFT_SQUARE);
                 self.consume();
             } else if current_char == ']' {
-                self.tokens.push(Token::RIGHT_SQUARE(self.make_location()));
+                self.symbol(Token::RIGHT_SQUARE);
 
                 count_of_squares -= 1;
 
                 if count_of_squares == -1 {
-                    report("Unexpected ].", self.make_location());
+                    self.error("Unexpected ].", self.make_location());
                 }
 
                 self.consume();
             } else if current_char == '{' {
-                self.tokens.push(Token::LEFT_CURLY(self.make_location()));
+                self.symbol(Token::LEFT_CURLY);
                 count_of_curlys += 1;
                 self.consume();
             } else if current_char == '}' {
-                self.tokens.push(Token::RIGHT_CURLY(self.make_location()));
+                self.symbol(Token::RIGHT_CURLY);
 
                 count_of_curlys -= 1;
 
                 if count_of_curlys == -1 {
-                    report("Unexpected }.", self.make_location());
+                    self.error("Unexpected }.", self.make_location());
                 }
 
                 self.consume();
@@ -133,11 +150,11 @@ impl Tokenizer {
         }
 
         if count_of_curlys != 0 {
-            report("Missing }", self.make_location());
+            self.error("Missing }", self.make_location());
         } else if count_of_brackets != 0 {
-            report("Missing )", self.make_location());
+            self.error("Missing )", self.make_location());
         } else if count_of_squares != 0 {
-            report("Missing ]", self.make_location());
+            self.error("Missing ]", self.make_location());
         }
 
         self.had_error
@@ -169,18 +186,20 @@ impl Tokenizer {
                 continue;
             } else if current_char == ' ' || current_char == '\t' {
                 self.consume();
+            } else if current_char == '.' && self.peek_next() == '.' {
+                self.rest_identifier();
             } else if current_char == '.' {
-                self.tokens.push(Token::DOT(self.make_location()));
+                self.symbol(Token::DOT);
                 self.consume();
             } else if current_char == ':' {
-                self.tokens.push(Token::COLON(self.make_location()));
+                self.symbol(Token::COLON);
                 self.consume();
             } else if current_char == '(' {
-                self.tokens.push(Token::LEFT_BRACKET(self.make_location()));
+                self.symbol(Token::LEFT_BRACKET);
                 count_of_brackets += 1;
                 self.consume();
             } else if current_char == ')' {
-                self.tokens.push(Token::RIGHT_BRACKET(self.make_location()));
+                self.symbol(Token::RIGHT_BRACKET);
                 count_of_brackets -= 1;
 
                 if count_of_brackets == -1 {
@@ -190,10 +209,10 @@ impl Tokenizer {
                 self.consume();
             } else if current_char == '[' {
                 count_of_squares += 1;
-                self.tokens.push(Token::LEFT_SQUARE(self.make_location()));
+                self.symbol(Token::LEFT_SQUARE);
                 self.consume();
             } else if current_char == ']' {
-                self.tokens.push(Token::RIGHT_SQUARE(self.make_location()));
+                self.symbol(Token::RIGHT_SQUARE);
 
                 count_of_squares -= 1;
 
@@ -203,11 +222,11 @@ impl Tokenizer {
 
                 self.consume();
             } else if current_char == '{' {
-                self.tokens.push(Token::LEFT_CURLY(self.make_location()));
+                self.symbol(Token::LEFT_CURLY);
                 count_of_curlys += 1;
                 self.consume();
             } else if current_char == '}' {
-                self.tokens.push(Token::RIGHT_CURLY(self.make_location()));
+                self.symbol(Token::RIGHT_CURLY);
 
                 count_of_curlys -= 1;
 
@@ -256,16 +275,50 @@ impl Tokenizer {
     }
 
     fn identifier(&mut self) {
+        let start = self.make_location();
         let mut identifier = String::new();
 
         while self.current_index < self.code.len() && self.peek() != ' ' && self.peek() != ':' && self.peek() != '(' && self.peek() != ')' && self.peek() != '.' && self.peek() != '\n' && self.peek() != ']' && self.peek() != '[' {
             identifier.push(self.consume());
         }
 
-        self.tokens.push(Token::IDENTIFIER(identifier, self.make_location()));
+        self.push(Token::IDENTIFIER(identifier, self.make_location()), start);
+    }
+
+    /// Tokenizes name of a rest parameter (...name) as an identifier.
+    fn rest_identifier(&mut self) {
+        let start = self.make_location();
+        let mut dots = String::new();
+
+        while self.peek() == '.' {
+            dots.push(self.consume());
+        }
+
+        if dots.len() != 3 {
+            self.error(&format!("Unexpected {}.", dots), start);
+            return;
+        }
+
+        self.identifier();
+
+        if let Some(Token::IDENTIFIER(identifier, _)) = self.tokens.last_mut() {
+            if identifier.is_empty() {
+                self.tokens.pop();
+                self.spans.pop();
+                self.error("Rest parameter requires a name after ...", start);
+                return;
+            }
+
+            identifier.insert_str(0, "...");
+        }
+
+        if let Some(span) = self.spans.last_mut() {
+            span.start = start;
+        }
     }
 
     fn number(&mut self) {
+        let start = self.make_location();
         let mut number_string = String::new();
 
         let mut had_error = false;
@@ -274,24 +327,27 @@ impl Tokenizer {
 
         while self.current_index < self.code.len() && self.peek() != ')' && self.peek() != ' ' && self.peek() != '\n' && self.peek() != ']' {
             if had_error {
+                // Skip the rest of the invalid number, so tokenizing can continue after it.
+                self.consume();
                 continue;
             }
 
+            let location = self.make_location();
             let current_char = self.consume();
             if Tokenizer::is_a_digit(current_char) {
                 if !is_first_character && current_char == '-' {
-                    report("- sign can be only present at the beginning of the number.", self.make_location());
+                    self.error("- sign can be only present at the beginning of the number.", location.clone());
                     had_error = true;
                 }
 
                 if is_first_character && current_char == '.' {
-                    report(". must not be present at the beginning of the number.", self.make_location());
+                    self.error(". must not be present at the beginning of the number.", location.clone());
                     had_error = true;
                 }
 
                 number_string.push(current_char);
             } else {
-                report(&format!("Character {} is disallowed in numbers. Only . - 0 1 2 3 4 5 6 7 8 9 characters are allowed.", current_char), self.make_location());
+                self.error(&format!("Character {} is disallowed in numbers. Only . - 0 1 2 3 4 5 6 7 8 9 characters are allowed.", current_char), location);
                 had_error = true;
             }
 
@@ -299,7 +355,7 @@ impl Tokenizer {
         }
 
         if number_string == "-" {
-            self.tokens.push(Token::IDENTIFIER("-".to_string(), self.make_location()));
+            self.push(Token::IDENTIFIER("-".to_string(), self.make_location()), start);
             return;
         }
 
@@ -307,18 +363,16 @@ impl Tokenizer {
             let number = number_string.parse::<f64>();
 
             match number {
-                Ok(number) => self.tokens.push(Token::NUMBER(number, self.make_location())),
+                Ok(number) => self.push(Token::NUMBER(number, self.make_location()), start),
                 Err(_) => {
-                    report(&format!("Number {} must have -?[0123456789]+(.[0123456789]+)? format.", number_string), self.make_location());
-                    self.had_error = true;
+                    self.error(&format!("Number {} must have -?[0123456789]+(.[0123456789]+)? format.", number_string), start);
                 }
             }
-        } else {
-            self.had_error = true;
         }
     }
 
     fn string(&mut self) {
+        let start = self.make_location();
         self.consume();
 
         let mut string = String::new();
@@ -335,11 +389,40 @@ impl Tokenizer {
         }
 
         if self.consume() != '"' {
-            report("Unterminated string.", self.make_location());
-            self.had_error = true;
+            self.error("Unterminated string.", start.clone());
         }
 
-        self.tokens.push(Token::STRING(string, self.make_location()));
+        self.push(Token::STRING(string, self.make_location()), start);
+    }
+
+    /// Adds a single character token at the current location.
+    fn symbol(&mut self, token: fn(Location) -> Token) {
+        let location = self.make_location();
+        self.spans.push(Span::at(location.clone()));
+        self.tokens.push(token(location));
+    }
+
+    /// Adds a token spanning from start to the last consumed character.
+    fn push(&mut self, token: Token, start: Location) {
+        let end = self.end_location(&start);
+        self.spans.push(Span::new(start, end));
+        self.tokens.push(token);
+    }
+
+    /// Records an error spanning from start to the last consumed character.
+    fn error(&mut self, message: &str, start: Location) {
+        let end = self.end_location(&start);
+        self.diagnostics.push(Diagnostic::error(message.to_string(), Span::new(start, end)));
+        self.had_error = true;
+    }
+
+    /// Location of the last consumed character, or start if nothing was consumed after it.
+    fn end_location(&self, start: &Location) -> Location {
+        if self.current_line == start.line_number && self.current_offset < start.offset {
+            start.clone()
+        } else {
+            Location::new(self.module_name.clone(), self.current_line, self.current_offset)
+        }
     }
 
     fn make_location(&self) -> Location {
@@ -13,7 +13,8 @@ pub enum Type {
     KEY_VALUE,
     TABLE,
     ERROR,
-    ENVIRONMENT
+    ENVIRONMENT,
+    FOREIGN(String)
 }
 
 impl Type {
@@ -31,12 +32,13 @@ impl Type {
             Type::KEY_VALUE => "KeyValue".to_string(),
             Type::TABLE => "Table".to_string(),
             Type::ERROR => "Error".to_string(),
-            Type::ENVIRONMENT => "Environment".to_string()
+            Type::ENVIRONMENT => "Environment".to_string(),
+            Type::FOREIGN(name) => name.clone()
         }
     }
 
-    pub fn get_for_name(name: &String) -> Option<Type> {
-        match name as &str {
+    pub fn get_for_name(name: &str) -> Option<Type> {
+        match name {
             "String" => Some(Type::STRING),
             "Number" => Some(Type::NUMBER),
             "Bool" => Some(Type::BOOL),
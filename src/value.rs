@@ -1,26 +1,37 @@
-use std::collections::HashMap;
+use std::any::Any;
 use std::fmt::{Debug, Formatter};
-use crate::environment::Environment;
-use crate::expression::Expression;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use crate::environment::{Closure, Environment};
+use crate::error::HierError;
+use crate::expression::Block;
+use crate::parameter::Parameter;
+use crate::table::Table;
 use crate::types::Type;
 
+/// Function implemented in Rust, which can capture state of the host.
+pub type NativeFunction = Rc<dyn Fn(&mut Environment, Vec<Value>) -> Result<Value, HierError>>;
+
 #[allow(non_camel_case_types)]
 #[derive(Clone)]
 pub enum Value {
     LIST(Vec<Value>),
     STRING(String),
     NUMBER(f64),
-    FUNCTION(Vec<String>, Box<Value>),
-    NATIVE_FUNCTION(fn(&mut Environment, Vec<Value>) -> Value, i64),
+    /// Function with parameters, body and scope in which it was defined.
+    FUNCTION(Vec<Parameter>, Box<Value>, Closure),
+    NATIVE_FUNCTION(NativeFunction, i64),
     BOOL(bool),
     NULL,
-    BLOCK(Vec<Expression>),
+    BLOCK(Rc<Block>),
     TYPE(Type),
-    FUNCTION_ARGUMENTS(Vec<String>),
-    KEY_VALUE(String, Box<Value>),
-    TABLE(HashMap<String, Value>),
+    FUNCTION_ARGUMENTS(Vec<Parameter>),
+    KEY_VALUE(Box<Value>, Box<Value>),
+    TABLE(Table),
     ERROR(String),
-    ENVIRONMENT(Box<Environment>)
+    ENVIRONMENT(Box<Environment>),
+    /// Object of the host, opaque to the code, with name of its type.
+    FOREIGN(Rc<dyn Any>, String)
 }
 
 impl Debug for Value {
@@ -41,7 +52,7 @@ impl Debug for Value {
             },
             Value::STRING(string) => write!(f, "{}", string),
             Value::NUMBER(number) => write!(f, "{}", number),
-            Value::FUNCTION(arguments, value) => write!(f, "Function {{ arguments: {:?}, value: {:?} }}", arguments, value),
+            Value::FUNCTION(arguments, value, _) => write!(f, "Function {{ arguments: {:?}, value: {:?} }}", arguments, value),
             Value::NATIVE_FUNCTION(_, _) => write!(f, "Native function"),
             Value::BOOL(boolean) => write!(f, "{}", boolean),
             Value::NULL => write!(f, "null"),
@@ -52,53 +63,80 @@ impl Debug for Value {
             Value::TABLE(table) => write!(f, "Table {{ table: {:?} }}", table),
             Value::ERROR(error) => write!(f, "Error {{ error: {:?} }}", error),
             Value::ENVIRONMENT(_) => write!(f, "Environment"),
+            Value::FOREIGN(_, type_name) => write!(f, "Foreign {{ type: {:?} }}", type_name),
         }
     }
 }
 
+/// Values are equal only if they have the same type. Lists, tables, key values and function arguments are compared by their
+/// contents, functions, blocks and foreign objects by identity and environments of modules by their global scopes.
 impl PartialEq<Self> for Value {
     fn eq(&self, other: &Self) -> bool {
-        if let Value::LIST(_) = self {
-            return false;
-        }
-
-        if let Value::LIST(_) = other {
-            return false;
+        match (self, other) {
+            (Value::NUMBER(number1), Value::NUMBER(number2)) => number1 == number2 || (number1.is_nan() && number2.is_nan()),
+            (Value::STRING(string1), Value::STRING(string2)) => string1 == string2,
+            (Value::BOOL(bool1), Value::BOOL(bool2)) => bool1 == bool2,
+            (Value::NULL, Value::NULL) => true,
+            (Value::LIST(list1), Value::LIST(list2)) => list1 == list2,
+            (Value::TABLE(table1), Value::TABLE(table2)) => table1 == table2,
+            (Value::KEY_VALUE(key1, value1), Value::KEY_VALUE(key2, value2)) => key1 == key2 && value1 == value2,
+            (Value::TYPE(type1), Value::TYPE(type2)) => type1 == type2,
+            (Value::ERROR(error1), Value::ERROR(error2)) => error1 == error2,
+            (Value::BLOCK(block1), Value::BLOCK(block2)) => Rc::ptr_eq(block1, block2),
+            (Value::FUNCTION(_, body1, closure1), Value::FUNCTION(_, body2, closure2)) => body1 == body2 && closure1 == closure2,
+            (Value::NATIVE_FUNCTION(function1, _), Value::NATIVE_FUNCTION(function2, _)) => Rc::ptr_eq(function1, function2),
+            (Value::FOREIGN(object1, _), Value::FOREIGN(object2, _)) => Rc::ptr_eq(object1, object2),
+            (Value::FUNCTION_ARGUMENTS(parameters1), Value::FUNCTION_ARGUMENTS(parameters2)) => parameters1 == parameters2,
+            (Value::ENVIRONMENT(environment1), Value::ENVIRONMENT(environment2)) => Rc::ptr_eq(&environment1.globals, &environment2.globals),
+            _ => false
         }
+    }
+}
 
-        if let Value::STRING(string1) = self {
-            return if let Value::STRING(string2) = other {
-                string1 == string2
-            } else {
-                false
-            }
-        }
+impl Eq for Value {
 
-        if let Value::BOOL(bool1) = self {
-            if let Value::BOOL(bool2) = other {
-                return bool1 == bool2;
-            } else {
-                return false;
-            }
-        }
+}
 
-        if let Value::ENVIRONMENT(_) = self {
-            return false;
-        }
+/// Hashes are consistent with equality, so numbers, strings and booleans can be used as keys of tables.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
 
-        if let Value::ENVIRONMENT(_) = other {
-            return false;
+        match self {
+            // Equal numbers (0 and -0, all NaNs) must have the same hash.
+            Value::NUMBER(number) if *number == 0f64 => 0f64.to_bits().hash(state),
+            Value::NUMBER(number) if number.is_nan() => f64::NAN.to_bits().hash(state),
+            Value::NUMBER(number) => number.to_bits().hash(state),
+            Value::STRING(string) => string.hash(state),
+            Value::BOOL(boolean) => boolean.hash(state),
+            Value::LIST(list) => list.hash(state),
+            Value::KEY_VALUE(key, value) => {
+                key.hash(state);
+                value.hash(state);
+            },
+            // Order of entries doesn't affect equality of tables.
+            Value::TABLE(table) => table.len().hash(state),
+            Value::ERROR(error) => error.hash(state),
+            _ => {}
         }
-
-        self.text_representation() == other.text_representation()
     }
 }
 
-impl Eq for Value {
+impl Value {
+    /// Wraps an object of the host, so it can be passed to the code.
+    pub fn foreign<T: Any>(object: T, type_name: &str) -> Value {
+        Value::FOREIGN(Rc::new(object), type_name.to_string())
+    }
 
-}
+    /// Returns the object of the host if the value is a foreign object of given type.
+    pub fn downcast_foreign<T: Any>(&self) -> Option<Rc<T>> {
+        if let Value::FOREIGN(object, _) = self {
+            object.clone().downcast::<T>().ok()
+        } else {
+            None
+        }
+    }
 
-impl Value {
     pub fn get_type(&self) -> Type {
         match self {
             Value::STRING(_) => Type::STRING,
@@ -106,7 +144,7 @@ impl Value {
             Value::BOOL(_) => Type::BOOL,
             Value::NULL => Type::NULL,
             Value::LIST(_) => Type::LIST,
-            Value::FUNCTION(_, _) => Type::FUNCTION,
+            Value::FUNCTION(_, _, _) => Type::FUNCTION,
             Value::BLOCK(_) => Type::BLOCK,
             Value::TYPE(_) => Type::NULL,
             Value::FUNCTION_ARGUMENTS(_) => Type::FUNCTION_ARGUMENTS,
@@ -114,7 +152,8 @@ impl Value {
             Value::TABLE(_) => Type::TABLE,
             Value::ERROR(_) => Type::ERROR,
             Value::NATIVE_FUNCTION(_, _) => Type::FUNCTION,
-            Value::ENVIRONMENT(_) => Type::ENVIRONMENT
+            Value::ENVIRONMENT(_) => Type::ENVIRONMENT,
+            Value::FOREIGN(_, type_name) => Type::FOREIGN(type_name.clone())
         }
     }
 
@@ -125,15 +164,16 @@ impl Value {
             Value::BOOL(value) => if *value { "true".to_string() } else { "false".to_string() },
             Value::NULL => "NULL".to_string(),
             Value::LIST(values) => self.text_representation_of_list(values),
-            Value::FUNCTION(_, _) => "<FUNCTION>".to_string(),
+            Value::FUNCTION(_, _, _) => "<FUNCTION>".to_string(),
             Value::BLOCK(_) => "<BLOCK>".to_string(),
             Value::TYPE(a_type) => a_type.text_representation(),
             Value::FUNCTION_ARGUMENTS(_) => "<FUNCTION_ARGUMENTS>".to_string(),
-            Value::KEY_VALUE(key, value) => format!("{}({})", key, value.text_representation()),
+            Value::KEY_VALUE(key, value) => format!("{}({})", key.text_representation(), value.text_representation()),
             Value::TABLE(_) => "<TABLE>".to_string(),
             Value::ERROR(error) => error.to_string(),
             Value::NATIVE_FUNCTION(_, _) => "<FUNCTION>".to_string(),
-            Value::ENVIRONMENT(_) => "<ENVIRONMENT>".to_string()
+            Value::ENVIRONMENT(_) => "<ENVIRONMENT>".to_string(),
+            Value::FOREIGN(_, type_name) => format!("<{}>", type_name)
         }
     }
 
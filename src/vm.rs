@@ -0,0 +1,116 @@
+use crate::bytecode::{Chunk, Instruction};
+use crate::environment::Environment;
+use crate::error::HierError;
+use crate::value::Value;
+
+/// Backend running the code. Both have the same semantics.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Backend {
+    /// Interprets expression trees directly.
+    TREE_WALKER,
+    /// Compiles blocks to bytecode once and runs it on a stack VM.
+    VM
+}
+
+impl Default for Backend {
+    /// VM if the vm feature is enabled, tree walker otherwise.
+    fn default() -> Self {
+        if cfg!(feature = "vm") {
+            Backend::VM
+        } else {
+            Backend::TREE_WALKER
+        }
+    }
+}
+
+impl Environment {
+    pub fn run_chunk(&mut self, chunk: &Chunk) -> Result<Value, HierError> {
+        let mut stack: Vec<Value> = Vec::new();
+        let mut index = 0;
+
+        while index < chunk.instructions.len() {
+            if let Some(location) = &chunk.locations[index] {
+                self.current_interpreting_location = location.clone();
+            }
+
+            match &chunk.instructions[index] {
+                Instruction::CONSTANT(value) => stack.push(value.clone()),
+                Instruction::STRING(string) => {
+                    let string = string.resolve(self)?;
+
+                    if self.signal.is_some() {
+                        return Ok(Value::NULL);
+                    }
+
+                    stack.push(Value::STRING(string));
+                },
+                Instruction::GET(name, depth) => {
+                    let value = match depth.and_then(|depth| self.get_at(depth, name)) {
+                        Some(value) => value,
+                        None => self.get(name.clone())?
+                    };
+
+                    stack.push(value);
+                },
+                Instruction::CALL(name, count, named) => {
+                    let arguments = stack.split_off(stack.len() - count);
+                    let result = self.call_function_with_named(name, arguments, named)?;
+
+                    if self.signal.is_some() {
+                        return Ok(Value::NULL);
+                    }
+
+                    stack.push(result);
+                },
+                Instruction::METHOD(name, count, named) => {
+                    let arguments = stack.split_off(stack.len() - count);
+                    let result = self.call_method(name, arguments, named)?;
+
+                    if self.signal.is_some() {
+                        return Ok(Value::NULL);
+                    }
+
+                    stack.push(result);
+                },
+                Instruction::LIST(count) => {
+                    let list = stack.split_off(stack.len() - count);
+                    stack.push(Value::LIST(list));
+                },
+                Instruction::PARAMETERS(parameters) => {
+                    let count = parameters.iter().filter(|parameter| parameter.default.is_some()).count();
+                    let mut defaults = stack.split_off(stack.len() - count).into_iter();
+                    let mut parameters = parameters.clone();
+
+                    for parameter in parameters.iter_mut().filter(|parameter| parameter.default.is_some()) {
+                        parameter.default = defaults.next();
+                    }
+
+                    stack.push(Value::FUNCTION_ARGUMENTS(parameters));
+                },
+                Instruction::KEY_VALUE(key) => {
+                    let value = stack.pop().unwrap_or(Value::NULL);
+                    stack.push(Value::KEY_VALUE(Box::new(Value::STRING(key.clone())), Box::new(value)));
+                },
+                Instruction::FUNCTION(length, block, end) => {
+                    if let Some(Value::FUNCTION_ARGUMENTS(_)) = stack.last() {
+                        let Some(Value::FUNCTION_ARGUMENTS(arguments)) = stack.pop() else { unreachable!() };
+
+                        stack.push(self.anonymous_function(arguments, *length, block.clone())?);
+                        index = *end;
+                        continue;
+                    }
+                },
+                Instruction::ERROR(message) => return Err(self.error(message)),
+                Instruction::STATEMENT => {
+                    stack.pop();
+                },
+                Instruction::RETURN => return Ok(stack.pop().unwrap_or(Value::NULL))
+            }
+
+            index += 1;
+        }
+
+        Ok(Value::NULL)
+    }
+}
\ No newline at end of file
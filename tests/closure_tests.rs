@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use libhier::hier::Hier;
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+/// Foreign value counting how many times it was dropped.
+struct Tracked(Rc<Cell<usize>>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn tracked(drops: &Rc<Cell<usize>>) -> Value {
+    Value::foreign(Tracked(drops.clone()), "Tracked")
+}
+
+/// Runs the code with a foreign value in variable o and returns how many times it was dropped with the instance.
+fn drops_after_run(code: &str) -> usize {
+    let drops = Rc::new(Cell::new(0));
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.add_variable("o".to_string(), tracked(&drops));
+
+    hier.try_run(code.to_string()).unwrap();
+    drop(hier);
+
+    drops.get()
+}
+
+#[test]
+fn counter_keeps_its_state() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.run("run {
+        (@make_counter (| ) {
+            (@count 0)
+            ((| ) { (=count (+ count 1)) count })
+        })
+        (@first (make_counter))
+        (@second (make_counter))
+        (first) (first) (second)
+        (& (first) (second))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3 2 ");
+}
+
+#[test]
+fn partial_application_captures_arguments() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.run("run {
+        (@adder (| a) { ((| b) { (+ a b) }) })
+        (@add_two (adder 2))
+        (@add_ten (adder 10))
+        (& (add_two 1) (add_ten 1))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3 11 ");
+}
+
+#[test]
+fn functions_do_not_see_variables_of_callers() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.run("run {
+        (@read_secret (| ) { secret })
+        (@call (| ) { (@secret \"caller\") (read_secret) })
+        (call)
+    }".to_string()).unwrap();
+
+    assert_eq!(value, Value::NULL);
+}
+
+#[test]
+fn recursive_functions_see_themselves() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.run("run {
+        (@factorial (| n) { (if (<= n 1) { 1 } { (* n (factorial (- n 1))) }) })
+        (factorial 5)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "120");
+}
+
+#[test]
+fn values_are_dropped_with_functions_capturing_them() {
+    assert_eq!(drops_after_run("(@x 1)"), 1);
+    assert_eq!(drops_after_run("(@f (| ) { o })"), 1);
+    assert_eq!(drops_after_run("(@mk (| o) { (@g (| ) { o }) 1 })"), 1);
+    assert_eq!(drops_after_run("run { (@make (| ) { (@count 0) ((| ) { o }) }) (@counter (make)) (counter) }"), 1);
+}
+
+#[test]
+fn calls_do_not_keep_their_scopes_alive() {
+    let drops = Rc::new(Cell::new(0));
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    hier.try_run("run { (@mk (| o) { (@g (| ) { o }) 1 }) (@get_inner (| o) { (@g (| ) { o }) g }) }".to_string()).unwrap();
+    hier.call("mk", vec![tracked(&drops)]).unwrap();
+
+    assert_eq!(drops.get(), 1);
+
+    // Inner function returned from the call keeps its scope alive.
+    let inner = hier.call("get_inner", vec![tracked(&drops)]).unwrap();
+    let value = hier.call_value(&inner, vec![]).unwrap();
+
+    assert!(value.downcast_foreign::<Tracked>().is_some());
+    assert_eq!(drops.get(), 1);
+
+    drop(value);
+    drop(inner);
+
+    assert_eq!(drops.get(), 2);
+}
+
+#[test]
+fn eval_keeps_variables_and_functions_of_the_caller() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run { (@q 1) (@h (| ) { q }) (eval \"2\") (list q (h)) }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 1 ");
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn equality_is_structural() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list
+        (== (1 2 (3 4)) (1 2 (3 4)))
+        (== (1 2) (1 2 3))
+        (== (list \"1\") (list 1))
+        (== (table a: (1 2)) (table a: (1 2)))
+        (== (table a: 1) (table a: 1 b: 2))
+        (!= (list (list)) (list (list))))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true false false true false false ");
+}
+
+#[test]
+fn key_values_are_compared_by_keys_and_values() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@entries (entries (table a: 1 b: 1)))
+        (list (== (get entries 0) (get (entries (table a: 1)) 0)) (== (get entries 0) (get entries 1)))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true false ");
+}
+
+#[test]
+fn strings_booleans_and_lists_are_ordered() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list
+        (< \"apple\" \"banana\")
+        (>= \"b\" \"abc\")
+        (< false true)
+        (< (1 2) (1 3))
+        (< (1 2) (1 2 0))
+        (<= (1 (2 3)) (1 (2 3)))
+        (> (list \"b\") (list \"a\" \"z\")))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true true true true true true true ");
+}
+
+#[test]
+fn values_of_different_types_are_not_ordered() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    assert!(hier.try_run("(< 1 \"2\")".to_string()).is_err());
+    assert!(hier.try_run("(> (1 2) (1 \"2\"))".to_string()).is_err());
+    assert!(hier.try_run("(<= (table a: 1) (table a: 2))".to_string()).is_err());
+    assert!(hier.try_run("(>= null null)".to_string()).is_err());
+}
+
+#[test]
+fn modules_and_function_arguments_are_equal_to_themselves() {
+    let mut hier = Hier::new(String::new(), |_| { "(@answer 42)".to_string() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@module (import \"module\"))
+        (list (== module module) (== module (import \"module\")) (== (| a b) (| a b)) (== (| a) (| b)))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true false true false ");
+}
\ No newline at end of file
@@ -0,0 +1,162 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+use libhier::output::Buffer;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn return_leaves_nested_blocks() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    let value = hier.try_run("run {
+        (@find (| list target) {
+            (for list {
+                (if (== element target) { (return \"found\") })
+                (print element)
+            })
+            \"missing\"
+        })
+        (+ (find (1 2 3 4) 3) \" \" (find (1 2) 5))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "found missing");
+    assert_eq!(output.contents(), "1212");
+}
+
+#[test]
+fn return_stops_evaluation_of_arguments() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    let value = hier.try_run("run {
+        (@first (| ) { (print (return 1) (print \"not printed\")) 2 })
+        (@second (| ) { (while { true } { (return) }) })
+        ((first) (second))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 NULL ");
+    assert_eq!(output.contents(), "");
+}
+
+#[test]
+fn return_outside_of_functions_returns_from_module() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run { (@a 1) (if true { (return a) }) 2 }".to_string()).unwrap();
+    assert_eq!(value.text_representation(), "1");
+
+    let value = hier.try_run("(+ 1 2)".to_string()).unwrap();
+    assert_eq!(value.text_representation(), "3");
+}
+
+#[test]
+fn break_exits_innermost_loop() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.try_run("run {
+        (for (1 2) {
+            (@outer element)
+            (repeat { (print outer) (break) })
+            (@i 0)
+            (while { true } { (=i (+ i 1)) (if (== i 3) { (break) }) })
+            (print i)
+        })
+    }".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "1323");
+}
+
+#[test]
+fn errors_named_like_break_do_not_exit_loops() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.try_run("(for (1 2 3) { (print (try (error \"LoopExit\") { \"caught\" })) (try (break) { (print \"not caught\") }) })".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "caught");
+}
+
+#[test]
+fn break_outside_of_loop_is_an_error() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let error = hier.try_run("run { (@f (| ) { (break) }) (for (1 2) { (f) }) }".to_string()).unwrap_err();
+
+    assert_eq!(error.message, "Break must be used inside of a loop.");
+}
+
+#[test]
+fn continue_skips_to_next_iteration() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.try_run("run {
+        (for (1 2 3 4) { (if (== (% element 2) 0) { (continue) }) (print element) })
+        (@i 0)
+        (while { (< i 4) } { (=i (+ i 1)) (if (== i 2) { (continue) }) (print i) })
+    }".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "13134");
+}
+
+#[test]
+fn labels_select_outer_loops() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.try_run("run {
+        (for label: \"rows\" (1 2 3) {
+            (@row element)
+            (for (1 2 3) {
+                (if (== element 2) { (continue label: \"rows\") })
+                (if (== row 3) { (break label: \"rows\") })
+                (print row element)
+            })
+        })
+        (repeat label: \"outer\" 2 { (repeat { (break label: \"outer\") }) (print \"not printed\") })
+    }".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "1121");
+}
+
+#[test]
+fn unknown_labels_are_errors() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let error = hier.try_run("(for label: \"a\" (1 2) { (continue label: \"b\") })".to_string()).unwrap_err();
+
+    assert_eq!(error.message, "Continue must be used inside of a loop labeled b.");
+}
+
+#[test]
+fn loops_evaluate_to_values() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@first_even (| list) { (for list { (if (== (% element 2) 0) { (break element) }) }) })
+        (@i 0)
+        (
+            (first_even (1 3 4 5 6))
+            (first_even (1 3))
+            (for (1 2 3) { (* element 10) })
+            (while { (< i 3) } { (=i (+ i 1)) })
+            (repeat label: \"outer\" { (repeat 3 { (break label: \"outer\" \"found\") }) })
+            (for (list) { 1 })
+        )
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "4 NULL 30 3 found NULL ");
+}
\ No newline at end of file
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use libhier::conversion::{FromValue, IntoValue};
+use libhier::error::HierError;
+use libhier::hier::Hier;
+use libhier::value::Value;
+
+#[test]
+fn values_convert_to_and_from_rust_types() {
+    assert_eq!(f64::from_value(&2.5.into_value()).unwrap(), 2.5);
+    assert_eq!(i64::from_value(&Value::NUMBER(3f64)).unwrap(), 3);
+    assert_eq!(String::from_value(&"text".into_value()).unwrap(), "text");
+    assert!(bool::from_value(&true.into_value()).unwrap());
+    assert_eq!(Vec::<f64>::from_value(&vec![1f64, 2f64].into_value()).unwrap(), vec![1f64, 2f64]);
+    assert_eq!(HashMap::<String, i64>::from_value(&HashMap::from([("a".to_string(), 1i64)]).into_value()).unwrap()["a"], 1);
+    assert_eq!(Option::<String>::from_value(&Value::NULL).unwrap(), None);
+    assert_eq!(Option::<f64>::from_value(&Value::NUMBER(1f64)).unwrap(), Some(1f64));
+}
+
+#[test]
+fn conversion_errors_describe_the_value() {
+    assert_eq!(f64::from_value(&Value::STRING("abc".to_string())).unwrap_err().message, "expected Number, but abc of type String was found");
+    assert_eq!(i64::from_value(&Value::NUMBER(1.5)).unwrap_err().message, "expected integer Number, but 1.5 of type Number was found");
+    assert_eq!(Vec::<bool>::from_value(&Value::LIST(vec![Value::BOOL(true), Value::NULL])).unwrap_err().message, "expected Bool, but NULL of type Null was found (element 1 of list)");
+}
+
+#[test]
+fn typed_functions_are_checked_and_converted() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+
+    hier.add_typed_function("add".to_string(), |a: f64, b: f64| a + b);
+    hier.add_typed_function("join".to_string(), |parts: Vec<String>, separator: Option<String>| parts.join(&separator.unwrap_or_default()));
+    hier.add_typed_function("half".to_string(), |number: i64| -> Result<i64, HierError> { Ok(number / 2) });
+
+    assert_eq!(hier.run("(add 1 2)".to_string()).unwrap().text_representation(), "3");
+    assert_eq!(hier.run("(join (& \"a\" \"b\") \"-\")".to_string()).unwrap().text_representation(), "a-b");
+    assert_eq!(hier.run("(join (& \"a\" \"b\") null)".to_string()).unwrap().text_representation(), "ab");
+    assert_eq!(hier.run("(half 4)".to_string()).unwrap().text_representation(), "2");
+
+    assert_eq!(hier.try_run("(add 1)".to_string()).unwrap_err().message, "Function add expects 2 arguments, but 1 were provided.");
+    assert_eq!(hier.try_run("(add 1 \"2\")".to_string()).unwrap_err().message, "Invalid argument 2 of function add: expected Number, but 2 of type String was found.");
+}
\ No newline at end of file
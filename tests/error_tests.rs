@@ -0,0 +1,101 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::diagnostic::Severity;
+use libhier::error::ErrorKind;
+use libhier::hier::Hier;
+use libhier::parser::Parser;
+use libhier::tokenizer::Tokenizer;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn runtime_errors_are_returned() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let error = hier.try_run("(/ 1 0)".to_string()).unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::RUNTIME);
+    assert_eq!(error.message, "Dividing by 0 is forbidden.");
+    assert_eq!(error.location.line_number, 1);
+}
+
+#[test]
+fn invalid_arguments_are_errors_instead_of_panics() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    for code in ["(+)", "(-)", "(*)", "(/)", "(% 1 0)", "(% 5 0.5)", "(remove (list))", "(remove \"\")", "(remove (list 1) 1)", "(replace (list 1) 5 1)",
+        "(replace \"ab\" -1 \"c\")", "(insert (list 1) 9 5)", "(insert \"a\" \"b\" 2)"] {
+        let error = hier.try_run(code.to_string()).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::RUNTIME, "{}", code);
+    }
+
+    let value = hier.try_run("(list (remove \"ab\") (replace \"ab\" 1 \"c\") (insert \"ab\" \"c\" 1) (insert (list 1) 2 1))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "a ac acb 1 2  ");
+}
+
+#[test]
+fn lex_errors_are_returned() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let error = hier.try_run("(print \"unterminated)".to_string()).unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::LEX);
+}
+
+#[test]
+fn environment_is_usable_after_error() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    assert!(hier.try_run("(@a (+ 1 true))".to_string()).is_err());
+
+    let value = hier.try_run("(+ 1 2)".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3");
+}
+
+#[test]
+fn all_tokenizer_diagnostics_are_collected() {
+    let mut tokenizer = Tokenizer::new("(print 1a 2b)".to_string());
+
+    assert!(tokenizer.tokenize_module());
+    assert_eq!(tokenizer.diagnostics.len(), 2);
+    assert_eq!(tokenizer.diagnostics[1].span.start.offset, 12);
+}
+
+#[test]
+fn warnings_are_collected() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    hier.try_run("(missing 1)".to_string()).unwrap();
+
+    assert_eq!(hier.diagnostics().len(), 1);
+    assert_eq!(hier.diagnostics()[0].severity, Severity::WARNING);
+}
+
+#[test]
+fn diagnostics_span_whole_tokens() {
+    let mut tokenizer = Tokenizer::new("(1) > print".to_string());
+    tokenizer.tokenize_module();
+
+    let mut parser = Parser::with_spans(tokenizer.tokens, tokenizer.spans);
+
+    assert!(parser.parse());
+    assert_eq!((parser.diagnostics[0].span.start.offset, parser.diagnostics[0].span.end.offset), (7, 11));
+
+    let mut tokenizer = Tokenizer::new("(print 12a4)".to_string());
+
+    assert!(tokenizer.tokenize_module());
+    assert_eq!((tokenizer.diagnostics[0].span.start.offset, tokenizer.diagnostics[0].span.end.offset), (10, 10));
+
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    hier.try_run("(print \"a\\qb\")".to_string()).unwrap_err();
+
+    assert_eq!((hier.diagnostics()[0].span.start.offset, hier.diagnostics()[0].span.end.offset), (10, 11));
+}
\ No newline at end of file
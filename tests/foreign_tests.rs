@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use libhier::hier::Hier;
+use libhier::types::Type;
+use libhier::value::Value;
+
+struct Counter {
+    name: String,
+    count: RefCell<f64>
+}
+
+fn hier_with_counter() -> Hier {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+
+    hier.add_function("counter".to_string(), 1, |_, arguments| {
+        Ok(Value::foreign(Counter { name: arguments[0].text_representation(), count: RefCell::new(0f64) }, "Counter"))
+    });
+
+    hier.add_foreign_property_getter("Counter".to_string(), |_, object, property| {
+        let counter = object.downcast_foreign::<Counter>().unwrap();
+
+        match property {
+            "name" => Ok(Value::STRING(counter.name.clone())),
+            "count" => Ok(Value::NUMBER(*counter.count.borrow())),
+            "increment" => Ok(Value::NATIVE_FUNCTION(Rc::new(|_, arguments| {
+                let counter = arguments[0].downcast_foreign::<Counter>().unwrap();
+                *counter.count.borrow_mut() += 1f64;
+                let count = *counter.count.borrow();
+                Ok(Value::NUMBER(count))
+            }), 1)),
+            _ => Ok(Value::NULL)
+        }
+    });
+
+    hier
+}
+
+#[test]
+fn foreign_values_survive_assignment_lists_and_tables() {
+    let mut hier = hier_with_counter();
+
+    let value = hier.run("run {
+        (@clicks (counter \"clicks\"))
+        (@list (& clicks))
+        (@table (table name: clicks))
+        (&& (== (get list 0) clicks) (== table.name clicks))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true");
+    assert_eq!(hier.run("(counter \"clicks\")".to_string()).unwrap().get_type(), Type::FOREIGN("Counter".to_string()));
+    assert_eq!(hier.run("(counter \"clicks\")".to_string()).unwrap().text_representation(), "<Counter>");
+    assert!(hier.run("(== (counter \"a\") (counter \"a\"))".to_string()).unwrap() == Value::BOOL(false));
+}
+
+#[test]
+fn foreign_properties_and_methods() {
+    let mut hier = hier_with_counter();
+
+    let value = hier.run("run {
+        (@clicks (counter \"clicks\"))
+        (clicks.increment)
+        (clicks.increment)
+        (+ clicks.name \" \" (string clicks.count))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "clicks 2");
+}
+
+#[test]
+fn foreign_values_are_handed_back_to_native_functions() {
+    let mut hier = hier_with_counter();
+
+    hier.add_function("name_of".to_string(), 1, |_, arguments| {
+        Ok(Value::STRING(arguments[0].downcast_foreign::<Counter>().unwrap().name.clone()))
+    });
+
+    let value = hier.run("(name_of (counter \"clicks\"))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "clicks");
+    assert!(value.downcast_foreign::<Counter>().is_none());
+}
\ No newline at end of file
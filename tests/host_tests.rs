@@ -0,0 +1,103 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use libhier::hier::Hier;
+use libhier::output::Buffer;
+use libhier::value::Value;
+
+#[test]
+fn native_functions_can_capture_state() {
+    let calls = Rc::new(Cell::new(0));
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+
+    let counter = calls.clone();
+    hier.add_function("count".to_string(), 0, move |_, _| {
+        counter.set(counter.get() + 1);
+        Ok(Value::NUMBER(counter.get() as f64))
+    });
+
+    let value = hier.run("run { (count) (count) (count) }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3");
+    assert_eq!(calls.get(), 3);
+}
+
+#[test]
+fn module_reader_can_capture_state() {
+    let mut modules = HashMap::new();
+    modules.insert("/library.hier".to_string(), "(@answer 42)".to_string());
+
+    let mut hier = Hier::new("/main.hier".to_string(), move |path| { modules.get(&path).cloned().unwrap_or_default() }, || {});
+
+    let value = hier.run("run { (@library (import \"library\")) (get library#answer) }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "42");
+}
+
+#[test]
+fn panic_is_returned_when_exit_handler_returns() {
+    let exited = Rc::new(Cell::new(false));
+    let handler_exited = exited.clone();
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, move || { handler_exited.set(true) });
+    hier.set_error_output(Buffer::new());
+
+    let error = hier.run("(panic \"Oh no\")".to_string()).unwrap_err();
+
+    assert!(exited.get());
+    assert_eq!(error.message, "Panic: Oh no");
+}
+
+struct Config {
+    greeting: String,
+    greeted: RefCell<Vec<String>>
+}
+
+#[test]
+fn native_functions_can_access_user_data() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+    hier.set_user_data(Config { greeting: "Hello".to_string(), greeted: RefCell::new(vec![]) });
+
+    hier.add_function("greet".to_string(), 1, |environment, arguments| {
+        let config = environment.user_data::<Config>().unwrap();
+        config.greeted.borrow_mut().push(arguments[0].text_representation());
+        Ok(Value::STRING(format!("{}, {}", config.greeting, arguments[0].text_representation())))
+    });
+
+    let value = hier.run("(greet \"World\")".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "Hello, World");
+    assert_eq!(*hier.user_data::<Config>().unwrap().greeted.borrow(), vec!["World".to_string()]);
+    assert!(hier.user_data::<String>().is_none());
+}
+
+#[test]
+fn functions_defined_by_code_are_called_from_host() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+
+    hier.run("run { (@greeting \"Hello\") (@greet (| name) { (+ greeting \", \" name) }) }".to_string()).unwrap();
+
+    assert_eq!(hier.call("greet", vec![Value::STRING("World".to_string())]).unwrap().text_representation(), "Hello, World");
+    assert_eq!(hier.get_variable("greeting").unwrap().text_representation(), "Hello");
+    assert_eq!(hier.get_variable("missing").unwrap(), Value::NULL);
+    assert_eq!(hier.call("greet", vec![]).unwrap_err().message, "Function greet expects 1 arguments, but 0 were provided.");
+}
+
+#[test]
+fn function_values_are_called_from_host() {
+    let handlers = Rc::new(RefCell::new(vec![]));
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, || {});
+
+    let registered = handlers.clone();
+    hier.add_function("on_event".to_string(), 1, move |_, arguments| {
+        registered.borrow_mut().push(arguments[0].clone());
+        Ok(Value::NULL)
+    });
+
+    hier.run("run { (@count 0) (on_event ((| x) { (=count (+ count x)) })) }".to_string()).unwrap();
+
+    let handler = handlers.borrow()[0].clone();
+    hier.call_value(&handler, vec![Value::NUMBER(2f64)]).unwrap();
+    hier.call_value(&handler, vec![Value::NUMBER(3f64)]).unwrap();
+
+    assert_eq!(hier.get_variable("count").unwrap(), Value::NUMBER(5f64));
+}
\ No newline at end of file
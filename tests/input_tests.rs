@@ -0,0 +1,45 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+use libhier::input::ScriptedInput;
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn read_uses_input_source() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.set_input(ScriptedInput::new(vec!["Hello".to_string(), "World".to_string()]));
+
+    let value = hier.run("(+ (read) \", \" (read))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "Hello, World");
+}
+
+#[test]
+fn read_returns_null_at_end_of_input() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.set_input(ScriptedInput::new(vec![]));
+
+    let value = hier.run("(read)".to_string()).unwrap();
+
+    assert!(matches!(value, Value::NULL));
+}
+
+#[test]
+fn closures_can_be_input_sources() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let mut count = 0;
+    hier.set_input(move || {
+        count += 1;
+        Ok(Some(count.to_string()))
+    });
+
+    let value = hier.run("(list (read) (read))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 2 ");
+}
\ No newline at end of file
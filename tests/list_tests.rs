@@ -0,0 +1,178 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn filter_find_any_and_all() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@even (| x) { (== (% x 2) 0) })
+        (list
+            (filter (1 2 3 4) { (> element 2) })
+            (filter (1 2 3 4) even)
+            (find (1 2 3 4) { (> element 1) })
+            (find (1 2) { (> element 5) })
+            (any (1 2 3) even)
+            (all (1 2 3) even)
+            (all (list) even))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3 4  2 4  2 NULL true false true ");
+}
+
+#[test]
+fn reduce_and_fold() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@add (| a b) { (+ a b) })
+        (list
+            (reduce (1 2 3 4) { (+ accumulator element) })
+            (fold (1 2 3) 10 add)
+            (reduce (list) add)
+            (reduce (list \"a\" \"b\") \">\" { (+ accumulator element) }))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "10 16 NULL >ab ");
+}
+
+#[test]
+fn sorting() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@descending (| a b) { (- b a) })
+        (list
+            (sort (3 1 2))
+            (sort (list \"pear\" \"apple\" \"fig\"))
+            (sort (3 1 2) { (- second first) })
+            (sort (3 1 2) descending)
+            (sort_by (list \"pear\" \"apple\" \"fig\") { (length element) })
+            (map (sort_by (list (table n: 2 id: \"a\") (table n: 1 id: \"b\") (table n: 2 id: \"c\")) { (get element \"n\") }) { (get element \"id\") }))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 2 3  apple fig pear  3 2 1  3 2 1  fig pear apple  b a c  ");
+}
+
+#[test]
+fn sorting_errors() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    assert_eq!(hier.try_run("(sort (1 \"a\"))".to_string()).unwrap_err().message, "Sort operation can't compare 1 and a.");
+    assert!(hier.try_run("(sort (1 2) { true })".to_string()).is_err());
+    assert!(hier.try_run("(sort (1 2) { (error \"failed\") })".to_string()).is_err());
+    assert!(hier.try_run("(sort_by (1 2) { (table) })".to_string()).is_err());
+
+    // Comparators which aren't consistent don't break sorting.
+    assert!(hier.try_run("(sort (5 3 8 1 9 2 7 4 6) { 1 })".to_string()).is_ok());
+}
+
+#[test]
+fn combining_lists() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list
+        (zip (1 2 3) (list \"a\" \"b\"))
+        (length (zip))
+        (flatten (1 (2 3) (list (list 4))))
+        (concat (1 2) (list) (list 3))
+        (reverse (1 2 3))
+        (reverse \"abc\")
+        (unique (1 2 1 3 2 (1 2) (1 2))))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 a  2 b   0 1 2 3 4   1 2 3  3 2 1  cba 1 2 3 1 2   ");
+}
+
+#[test]
+fn enumerate_gives_indices_as_keys() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@total 0)
+        (for (enumerate (list \"a\" \"b\" \"c\")) { (=total (+ total element.key)) })
+        (list total (get (enumerate (list \"a\")) 0).value)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3 a ");
+}
+
+#[test]
+fn slicing_and_searching() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list
+        (slice (1 2 3 4) 1 3)
+        (slice (1 2 3 4) 2)
+        (slice \"hello\" 1 4)
+        (index_of (1 2 3) 3)
+        (index_of (1 2 3) \"3\")
+        (index_of \"hello\" \"ll\")
+        (contains (1 (2 3)) (2 3))
+        (contains \"hello\" \"x\"))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "2 3  3 4  ell 2 NULL 2 true false ");
+
+    assert!(hier.try_run("(slice (1 2) 1 3)".to_string()).is_err());
+    assert!(hier.try_run("(slice (1 2) 2 1)".to_string()).is_err());
+}
+
+#[test]
+fn strings_are_measured_in_characters() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list
+        (length \"héllo\")
+        (slice \"héllo\" 0 (length \"héllo\"))
+        (slice \"héllo\" 1 3)
+        (index_of \"héllo\" \"l\"))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "5 héllo él 2 ");
+
+    assert_eq!(hier.try_run("(get \"héllo\" 4)".to_string()).unwrap().text_representation(), "o");
+    assert!(hier.try_run("(get \"héllo\" 5)".to_string()).is_err());
+}
+
+#[test]
+fn functions_chain_with_pipes() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(5 3 8 1 4) > (filter { (> element 2) }) > (sort) > (map { (* element 10) }) > (reduce { (+ accumulator element) })".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "200");
+}
+
+#[test]
+fn break_and_return_stop_callbacks() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@first_big (| list) { (filter list { (if (> element 2) { (return element) }) false }) })
+        (@looped (repeat 3 { (sort (3 1 2) { (break 7) }) }))
+        (list (first_big (1 5 9)) looped)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "5 7 ");
+}
+
+#[test]
+fn declared_functions_shadow_built_in_ones() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.add_function("reverse".to_string(), 1, |_, arguments| Ok(arguments[0].clone()));
+
+    let value = hier.try_run("run {
+        (@sort (| list) { \"sorted\" })
+        (@filter (| list block) { (length list) })
+        (@any 1)
+        (=any (| x) { x })
+        (list (sort (3 1 2)) (filter (1 2 3) { element }) (any 5) (reverse (1 2)) (map (1 2) { (* element 2) }))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "sorted 3 5 1 2  2 4  ");
+}
\ No newline at end of file
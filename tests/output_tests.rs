@@ -0,0 +1,46 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+use libhier::output::Buffer;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn print_writes_to_output() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.run("run { (print 1 \" \" 2) (println \"!\") (println true) }".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "1 2!\ntrue\n");
+}
+
+#[test]
+fn diagnostics_are_reported_to_error_output() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    let error_output = Buffer::new();
+    hier.set_output(output.clone());
+    hier.set_error_output(error_output.clone());
+
+    hier.run("(missing 1)".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "");
+    assert!(error_output.contents().ends_with("?: Function missing doesn't exist or is not a function.\n"));
+}
+
+#[test]
+fn eval_shares_output() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+
+    hier.run("(eval \"(print 5)\")".to_string()).unwrap();
+
+    assert_eq!(output.contents(), "5");
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+use libhier::parameter::Parameter;
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn default_values_are_used_for_missing_arguments() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@base 10)
+        (@add (| a b: base c:1) { (+ a b c) })
+        (=base 100)
+        ((add 1) (add 1 2) (add 1 2 3))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "12 4 6 ");
+}
+
+#[test]
+fn rest_parameter_receives_remaining_arguments() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@count (| first ...rest) { (+ (* first 10) (length rest)) })
+        (@all (| ...all) { all })
+        ((count 1) (count 1 2 3) (length (all)) (all 1 2))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "10 12 0 1 2  ");
+}
+
+#[test]
+fn arity_errors_describe_accepted_range() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.try_run("run { (@exact (| a b) { a }) (@optional (| a b: 1 c: 2) { a }) (@variadic (| a ...rest) { a }) }".to_string()).unwrap();
+
+    let message = |hier: &mut Hier, code: &str| hier.try_run(code.to_string()).unwrap_err().message;
+
+    assert_eq!(message(&mut hier, "(exact 1)"), "Function exact expects 2 arguments, but 1 were provided.");
+    assert_eq!(message(&mut hier, "(optional)"), "Function optional expects 1 to 3 arguments, but 0 were provided.");
+    assert_eq!(message(&mut hier, "(variadic)"), "Function variadic expects at least 1 arguments, but 0 were provided.");
+}
+
+#[test]
+fn invalid_parameter_lists_are_errors() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let message = |hier: &mut Hier, code: &str| hier.try_run(code.to_string()).unwrap_err().message;
+
+    assert_eq!(message(&mut hier, "(| ...rest a)"), "Rest parameter rest must be the last parameter.");
+    assert_eq!(message(&mut hier, "(| a: 1 b)"), "Parameter b without a default value must not follow parameter a with one.");
+    assert_eq!(message(&mut hier, "(| 1)"), "Function arguments must be identifiers.");
+    assert_eq!(message(&mut hier, "(| a ...)"), "Rest parameter requires a name after ...");
+    assert_eq!(message(&mut hier, "(print 1 ...rest)"), "Rest parameter ...rest can be only declared in a function arguments list (| ...).");
+    assert_eq!(message(&mut hier, "run { ...rest }"), "Rest parameter ...rest can be only declared in a function arguments list (| ...).");
+}
+
+#[test]
+fn named_arguments_are_bound_by_name() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@connect (| host port: 80 secure: false) { (list host port secure) })
+        (@first (| pair) { pair })
+        (
+            (connect port: 8080 host: \"a\")
+            (connect \"b\" secure: true)
+            (for (table key: 1) { (first element) })
+        )
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "a 8080 false  b 80 true  key(1) ");
+}
+
+#[test]
+fn invalid_named_arguments_are_errors() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.try_run("(@connect (| host port: 80) { host })".to_string()).unwrap();
+
+    let message = |hier: &mut Hier, code: &str| hier.try_run(code.to_string()).unwrap_err().message;
+
+    assert_eq!(message(&mut hier, "(connect \"a\" user: \"b\")"), "Function connect has no parameter named user.");
+    assert_eq!(message(&mut hier, "(connect \"a\" host: \"b\")"), "Argument host of function connect was provided more than once.");
+    assert_eq!(message(&mut hier, "(connect port: 1)"), "Function connect is missing argument host.");
+    assert_eq!(message(&mut hier, "(connect \"a\" 1 2)"), "Function connect expects 1 to 2 arguments, but 3 were provided.");
+}
+
+#[test]
+fn native_functions_receive_named_arguments_as_key_values() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    hier.add_function("describe".to_string(), -1, |environment, arguments| {
+        let named = environment.named_argument_indices().to_vec();
+        let (positional, named) = Parameter::split_named(arguments, &named).map_err(|error| environment.error(&error))?;
+        Ok(Value::STRING(format!("{} {}", positional.len(), named.get("size").map(Value::text_representation).unwrap_or_default())))
+    });
+
+    let value = hier.try_run("(list (describe 1 size: 2 3) (describe (get (entries (table size: 1)) 0) size: 4))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "2 2 1 4 ");
+    assert_eq!(hier.try_run("(describe size: 1 size: 2)".to_string()).unwrap_err().message, "Argument size was provided more than once.");
+}
\ No newline at end of file
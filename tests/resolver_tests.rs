@@ -0,0 +1,96 @@
+use libhier::error::ErrorKind;
+use libhier::expression::Expression;
+use libhier::hier::Hier;
+use libhier::parser::Parser;
+use libhier::resolver::Resolver;
+use libhier::tokenizer::Tokenizer;
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+fn resolve(code: &str) -> (Expression, Resolver) {
+    let mut tokenizer = Tokenizer::new(code.to_string());
+    tokenizer.tokenize_module();
+
+    let mut parser = Parser::new(tokenizer.tokens);
+    parser.parse();
+
+    let mut resolver = Resolver::new(false);
+    resolver.resolve(&mut parser.code);
+
+    (parser.code, resolver)
+}
+
+fn depths(expression: &Expression, depths_of: &mut Vec<(String, Option<usize>)>) {
+    match expression {
+        Expression::IDENTIFIER(name, _, depth) => depths_of.push((name.clone(), *depth)),
+        Expression::LIST(expressions, _) => {
+            for expression in expressions {
+                depths(expression, depths_of);
+            }
+        },
+        Expression::BLOCK(block, _) => {
+            for expression in &block.expressions {
+                depths(expression, depths_of);
+            }
+        },
+        Expression::PROPERTY(expression, _, _) | Expression::KEY_VALUE(_, expression, _) => depths(expression, depths_of),
+        _ => {}
+    }
+}
+
+#[test]
+fn identifiers_are_resolved_to_depths() {
+    let (code, resolver) = resolve("(run { (@a 1) (@f (| b) { (if true { (+ a b host) }) }) })");
+    let mut found = vec![];
+    depths(&code, &mut found);
+
+    let a = found.iter().find(|(name, _)| name == "a").unwrap();
+    let b = found.iter().rev().find(|(name, _)| name == "b").unwrap();
+    let host = found.iter().find(|(name, _)| name == "host").unwrap();
+
+    assert_eq!(a.1, Some(2));
+    assert_eq!(b.1, Some(1));
+    assert_eq!(host.1, None);
+    assert!(resolver.diagnostics.is_empty());
+}
+
+#[test]
+fn duplicate_declarations_are_reported_before_running() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let error = hier.try_run("run { (print \"not printed\") (@a 1) (@a 2) }".to_string()).unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::RESOLVE);
+    assert_eq!(error.message, "Variable 'a' already exists in current scope.");
+}
+
+#[test]
+fn use_before_declaration_is_a_warning() {
+    let (_, resolver) = resolve("(run { (@f (| ) { later }) (print early) (@early 1) (@later 2) })");
+
+    assert_eq!(resolver.diagnostics.len(), 1);
+    assert!(!resolver.diagnostics[0].is_error());
+    assert_eq!(resolver.diagnostics[0].message, "Variable early is used before it is declared.");
+}
+
+#[test]
+fn variables_declared_by_code_resolver_cant_follow_are_found() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    hier.add_function("run_here".to_string(), 1, |environment, arguments| {
+        if let Value::BLOCK(block) = arguments[0].clone() {
+            environment.interpret_block(block)
+        } else {
+            Ok(Value::NULL)
+        }
+    });
+
+    hier.add_variable("host".to_string(), Value::NUMBER(3f64));
+
+    let value = hier.run("run { (@x 1) (if true { (run_here { (@x 2) }) (+ x host) }) }".to_string()).unwrap();
+
+    assert_eq!(value, Value::NUMBER(5f64));
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+#![cfg(feature = "serde")]
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use libhier::hier::Hier;
+use libhier::serialization::{from_value, to_value};
+use libhier::value::Value;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Mode {
+    Fast,
+    Limited(u32)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Config {
+    name: String,
+    retries: i32,
+    ratio: f64,
+    enabled: bool,
+    tags: Vec<String>,
+    parent: Option<String>,
+    mode: Mode
+}
+
+#[test]
+fn structs_round_trip_through_values() {
+    let config = Config { name: "main".to_string(), retries: 3, ratio: 0.5, enabled: true, tags: vec!["a".to_string()], parent: None, mode: Mode::Limited(2) };
+
+    let value = to_value(&config).unwrap();
+
+    if let Value::TABLE(table) = &value {
+        assert_eq!(table["retries"], Value::NUMBER(3f64));
+        assert_eq!(table["parent"], Value::NULL);
+    } else {
+        panic!("Config was not serialized to a table.");
+    }
+
+    assert_eq!(from_value::<Config>(value).unwrap(), config);
+    assert_eq!(to_value(&Mode::Fast).unwrap(), Value::STRING("Fast".to_string()));
+}
+
+#[test]
+fn values_are_injected_and_extracted() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let config = Config { name: "main".to_string(), retries: 3, ratio: 0.5, enabled: true, tags: vec![], parent: None, mode: Mode::Fast };
+
+    hier.add_variable("config".to_string(), to_value(&config).unwrap());
+
+    let value = hier.run("(table name: (get config \"name\") retries: (+ config.retries 1) values: (& 1 2.5))".to_string()).unwrap();
+    let result: HashMap<String, Value> = from_value(value.clone()).unwrap();
+
+    assert_eq!(result["name"], Value::STRING("main".to_string()));
+    assert_eq!(from_value::<i64>(result["retries"].clone()).unwrap(), 4);
+    assert_eq!(from_value::<Vec<f64>>(result["values"].clone()).unwrap(), vec![1f64, 2.5]);
+    assert!(from_value::<Config>(value).unwrap_err().message.contains("missing field"));
+}
+
+#[test]
+fn map_keys_keep_their_types() {
+    let scores = HashMap::from([(1, "one".to_string()), (2, "two".to_string())]);
+
+    let value = to_value(&scores).unwrap();
+
+    if let Value::TABLE(table) = &value {
+        assert_eq!(table.get(&Value::NUMBER(1f64)), Some(&Value::STRING("one".to_string())));
+        assert_eq!(table.get(&Value::STRING("1".to_string())), None);
+    } else {
+        panic!("Map was not serialized to a table.");
+    }
+
+    assert_eq!(from_value::<HashMap<i32, String>>(value).unwrap(), scores);
+}
+
+#[test]
+fn only_keys_are_deserialized_as_table_keys() {
+    let entry = Value::KEY_VALUE(Box::new(Value::LIST(vec![])), Box::new(Value::NUMBER(1f64)));
+
+    assert!(from_value::<Value>(entry).unwrap_err().message.contains("table key"));
+}
\ No newline at end of file
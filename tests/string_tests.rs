@@ -2,7 +2,6 @@
 
 extern crate core;
 
-use libhier;
 use libhier::hier::Hier;
 
 fn exit_handler() -> ! {
@@ -13,7 +12,7 @@ fn exit_handler() -> ! {
 fn escape_sequences_are_resolved() {
     let mut hier = Hier::new(String::new(),|_| { String::new() }, exit_handler);
 
-    let value = hier.run("(get \"Test \\n\")".to_string());
+    let value = hier.run("(get \"Test \\n\")".to_string()).unwrap();
 
     assert_eq!(value.text_representation(), "Test \n");
 }
@@ -22,16 +21,38 @@ fn escape_sequences_are_resolved() {
 fn interpolations_are_resolved() {
     let mut hier = Hier::new(String::new(),|_| { String::new() }, exit_handler);
 
-    let value = hier.run("(run (@test 5) (get \"Test \\(get test)\"))".to_string());
+    let value = hier.run("(run (@test 5) (get \"Test \\(get test)\"))".to_string()).unwrap();
 
     assert_eq!(value.text_representation(), "Test 5");
 }
 
+#[test]
+fn text_after_interpolations_is_kept() {
+    let mut hier = Hier::new(String::new(),|_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(get \"v=\\(+ 1 2)! \\(+ 2 2)\\n\\\\end\")".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "v=3! 4\n\\end");
+    assert!(hier.try_run("(get \"\\(+ 1 2)\\q\")".to_string()).is_err());
+}
+
 #[test]
 fn is_concatenating_properly() {
     let mut hier = Hier::new(String::new(),|_| { String::new() }, exit_handler);
 
-    let value = hier.run("(+ \"Test\" \" concatenation\")".to_string());
+    let value = hier.run("(+ \"Test\" \" concatenation\")".to_string()).unwrap();
 
     assert_eq!(value.text_representation(), "Test concatenation");
+}
+
+#[test]
+fn diagnostics_in_interpolations_are_located_in_the_file() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    hier.try_run("run {\n  (@a 1)\n  (print \"x \\(+ a })\")\n}".to_string()).unwrap_err();
+
+    let span = &hier.diagnostics()[0].span;
+
+    assert_eq!(hier.diagnostics()[0].message, "Unexpected }.");
+    assert_eq!((span.start.line_number, span.start.offset, span.end.offset), (3, 19, 19));
 }
\ No newline at end of file
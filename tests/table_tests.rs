@@ -0,0 +1,128 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use libhier::hier::Hier;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+#[test]
+fn set_and_delete_return_new_tables() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@point (table x: 1 y: 2))
+        (@moved (set point \"x\" 5))
+        (@flat (delete moved \"y\"))
+        (list point.x moved.x moved.y flat.y)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 5 2 NULL ");
+}
+
+#[test]
+fn has_keys_values_and_entries() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@point (table y: 2 x: 1 z: 3))
+        ((has point \"x\") (has point \"w\") (keys point) (point.values) (length (entries point)) (get (entries point) 0).key)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "true false y x z  2 1 3  3 y ");
+}
+
+#[test]
+fn tables_keep_insertion_order() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@t (table zebra: 1 apple: 2 mango: 3 kiwi: 4))
+        (=t.apple 5)
+        (=t (delete t \"mango\"))
+        (=t.banana 6)
+        (@order \"\")
+        (for t { (=order (+ order element.key \"=\" (string element.value) \" \")) })
+        order
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "zebra=1 apple=5 kiwi=4 banana=6 ");
+    assert_eq!(format!("{:?}", hier.try_run("(table b: 1 a: 2)".to_string()).unwrap()), "Table { table: {b: 1, a: 2} }");
+}
+
+#[test]
+fn keys_can_be_numbers_strings_and_booleans() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@t (set (set (set (table) 1 \"number\") \"1\" \"string\") true \"bool\"))
+        (list t[1] t[\"1\"] (get t true) (get t 2) (has t 1) (has t false) (is (get (keys t) 0) Number))
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "number string bool NULL true false true ");
+}
+
+#[test]
+fn equality_is_type_strict() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("(list (== 1 \"1\") (== true \"true\") (== 1 1) (!= \"a\" \"a\") (== (table a: 1 b: 2) (table b: 2 a: 1)))".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "false false true false true ");
+}
+
+#[test]
+fn merge_overrides_earlier_tables() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@merged (merge (table a: 1 b: 2) (table b: 3) (table c: 4)))
+        (values merged)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "1 3 4 ");
+}
+
+#[test]
+fn property_assignment() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@config (table server: (table port: 80)))
+        (=config.name \"web\")
+        (=config.server.port 8080)
+        (list config.name config.server.port)
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "web 8080 ");
+}
+
+#[test]
+fn table_operation_errors() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    assert!(hier.try_run("(set (list 1) \"a\" 1)".to_string()).is_err());
+    assert!(hier.try_run("(delete (table a: 1) (list 1))".to_string()).is_err());
+    assert!(hier.try_run("(keys 1)".to_string()).is_err());
+    assert!(hier.try_run("(merge (table a: 1) 2)".to_string()).is_err());
+    assert!(hier.try_run("run { (@t (table a: 1)) (=t.a 1 2) }".to_string()).is_err());
+}
+
+#[test]
+fn declared_functions_shadow_table_functions() {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+
+    let value = hier.try_run("run {
+        (@set (| x) { x })
+        (@get (| x) { \"mine\" })
+        (@has (| ) { \"own\" })
+        (@t (table a: (table b: 1)))
+        (@l (list 5 6))
+        (=t.a.b 2)
+        (list (set 3) (get 1) (has) t.a.b l[1])
+    }".to_string()).unwrap();
+
+    assert_eq!(value.text_representation(), "3 mine own 2 6 ");
+}
\ No newline at end of file
@@ -0,0 +1,85 @@
+#![allow(unreachable_code)]
+
+extern crate core;
+
+use std::rc::Rc;
+use libhier::expression::Expression;
+use libhier::hier::Hier;
+use libhier::output::Buffer;
+use libhier::parser::Parser;
+use libhier::tokenizer::Tokenizer;
+use libhier::value::Value;
+use libhier::vm::Backend;
+
+fn exit_handler() -> ! {
+    panic!("")
+}
+
+/// Runs the code on given backend, returning printed output and the result (or error with its location).
+fn run_on(backend: Backend, code: &str) -> (String, String) {
+    let mut hier = Hier::new(String::new(), |_| { String::new() }, exit_handler);
+    let output = Buffer::new();
+    hier.set_output(output.clone());
+    hier.set_backend(backend);
+
+    let result = match hier.try_run(code.to_string()) {
+        Ok(value) => value.text_representation(),
+        Err(error) => error.to_string()
+    };
+
+    (output.contents(), result)
+}
+
+fn assert_same_on_backends(code: &str) {
+    assert_eq!(run_on(Backend::TREE_WALKER, code), run_on(Backend::VM, code), "{}", code);
+}
+
+#[test]
+fn backends_produce_same_results() {
+    assert_same_on_backends("run {
+        (@fib (| n) {
+            (if (< n 2) { n } { (+ (fib (- n 1)) (fib (- n 2))) })
+        })
+        (@total 0)
+        (for (1 2 3 4) { (=total (+ total (fib element))) })
+        (@i 0)
+        (while { (< i 3) } { (=i (+ i 1)) (print i) })
+        (print \"total \\(total)\")
+        (map (1 2 3) { (* element 2) })
+    }");
+    assert_same_on_backends("run {
+        (@make_counter (| ) { (@count 0) ((| ) { (=count (+ count 1)) }) })
+        (@next (make_counter))
+        (next) (next)
+        ((next) key: 1 (table a: 2))
+    }");
+    assert_same_on_backends("(repeat 5 { (print 1) (break) (print 2) })");
+    assert_same_on_backends("run { (@f (| ) { (print \"a\" (return 1) \"b\") 2 }) (f) }");
+    assert_same_on_backends("run { (@odd (| x) { (== (% x 2) 1) }) ((filter (3 1 2) odd) (sort (3 1 2) { (- second first) }) (reduce (1 2 3) { (+ accumulator element) })) }");
+}
+
+#[test]
+fn backends_report_errors_at_same_location() {
+    assert_same_on_backends("run {\n (@a 1)\n (+ a\n true) }");
+    assert_same_on_backends("run {\n (print 1)\n (missing) (/ 1 0) }");
+    assert_same_on_backends("run {\n ((| a 1) { a }) }");
+    assert_same_on_backends("run {\n ((| a) (+ 1 2)) }");
+    assert_same_on_backends("run {\n (@f (| a b: (+ 1 \"x\")) { a }) }");
+    assert_same_on_backends("run {\n (@f (| a b: 2 ...c) { (a b c) }) ((f 1) (f 1 3 4 5) (f)) }");
+    assert_same_on_backends("run {\n (@f (| a b: 2) { (list a b) }) ((f b: 3 a: 1) (f 1 c: 2)) }");
+    assert_same_on_backends("run {\n (@t (table a: 1))\n (=t.b.c 2)\n (=t.a 3) (keys t) }");
+}
+
+#[test]
+fn blocks_are_compiled_once() {
+    let mut tokenizer = Tokenizer::new("{ (+ 1 2) }".to_string());
+    tokenizer.tokenize_module();
+    let mut parser = Parser::new(tokenizer.tokens);
+    parser.parse();
+
+    let Expression::BLOCK(block, _) = parser.code else { panic!("expected a block") };
+    let shared = Value::BLOCK(block.clone());
+
+    let Value::BLOCK(shared) = shared else { unreachable!() };
+    assert!(Rc::ptr_eq(&block.chunk(), &shared.chunk()));
+}
\ No newline at end of file